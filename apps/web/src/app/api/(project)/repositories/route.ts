@@ -8,6 +8,10 @@ import { ensureSkillFile } from "@/lib/project";
 // Server mode: when deployed (e.g., Vercel), will use database instead of filesystem
 const SERVER_MODE = process.env.SERVER_MODE === "true";
 
+// The test routes keep the tests of every repository here, whatever their
+// `?repo=`; reported so clients reading test files use the same folder.
+const TESTS_DIR = path.join(process.cwd(), ".agelum/tests");
+
 export async function GET() {
   try {
     if (SERVER_MODE) {
@@ -73,6 +77,7 @@ export async function GET() {
         repositories,
         basePath: "",
         serverMode: false,
+        testsDir: TESTS_DIR,
       });
     }
 
@@ -98,7 +103,12 @@ export async function GET() {
       }
     }
 
-    return NextResponse.json({ repositories, basePath, serverMode: false });
+    return NextResponse.json({
+      repositories,
+      basePath,
+      serverMode: false,
+      testsDir: TESTS_DIR,
+    });
   } catch (error) {
     return NextResponse.json(
       {
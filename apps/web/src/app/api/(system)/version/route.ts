@@ -0,0 +1,67 @@
+import { NextResponse } from "next/server";
+import packageJson from "../../../../../package.json";
+
+// Lets clients such as the CLI check which version of the app they talk to,
+// and whether the routes they call exist. Keep the list in sync with the
+// route.ts files under src/app/api.
+const ROUTES = [
+  "/api/agents",
+  "/api/agents/input",
+  "/api/api-keys",
+  "/api/app-logs",
+  "/api/app-status",
+  "/api/epics",
+  "/api/file",
+  "/api/files",
+  "/api/files/search",
+  "/api/files/text-search",
+  "/api/gateway",
+  "/api/gateway/callback",
+  "/api/gateway/login",
+  "/api/generate-commit",
+  "/api/git",
+  "/api/github",
+  "/api/history",
+  "/api/ideas",
+  "/api/image",
+  "/api/mcp",
+  "/api/opencode",
+  "/api/opencode/message",
+  "/api/project/cache/favicons",
+  "/api/project/config",
+  "/api/repositories",
+  "/api/settings",
+  "/api/system-files/files",
+  "/api/system/command",
+  "/api/tasks",
+  "/api/tasks/link",
+  "/api/terminal",
+  "/api/tests",
+  "/api/tests/[id]",
+  "/api/tests/[id]/executions",
+  "/api/tests/[id]/finish",
+  "/api/tests/[id]/run",
+  "/api/tests/[id]/steps",
+  "/api/tests/artifacts/[...path]",
+  "/api/tests/executions",
+  "/api/tests/groups",
+  "/api/tests/record/ai",
+  "/api/tests/record/capture",
+  "/api/tests/record/execute",
+  "/api/tests/run",
+  "/api/tests/status",
+  "/api/upload",
+  "/api/usage",
+  "/api/users",
+  "/api/validate-path",
+  "/api/version",
+];
+
+export async function GET() {
+  return NextResponse.json({
+    name: "agelum",
+    version: packageJson.version,
+    serverMode: process.env.SERVER_MODE === "true",
+    routes: ROUTES,
+  });
+}
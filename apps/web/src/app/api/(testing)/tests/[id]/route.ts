@@ -77,6 +77,7 @@ export async function PUT(
       id,
       name: body.name,
       steps: body.steps,
+      ...(body.browser ? { browser: body.browser } : {}),
     };
 
     fs.writeFileSync(filePath, JSON.stringify(testContent, null, 2));
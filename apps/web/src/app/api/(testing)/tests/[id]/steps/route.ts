@@ -97,3 +97,54 @@ export async function POST(
     return NextResponse.json({ error: error.message }, { status: 500 });
   }
 }
+
+export async function PUT(
+  request: Request,
+  { params }: { params: Promise<{ id: string }> },
+) {
+  try {
+    const { id } = await params;
+    if (!id) return NextResponse.json({ error: "Missing ID" }, { status: 400 });
+
+    const steps = await request.json();
+    if (!Array.isArray(steps)) {
+      return NextResponse.json(
+        { error: "An array of steps is required" },
+        { status: 400 },
+      );
+    }
+
+    const filePath = resolveTestPath(id);
+    if (!filePath) {
+      return NextResponse.json({ error: "Test not found" }, { status: 404 });
+    }
+
+    // Only the steps are replaced; the rest of the test is kept as is
+    const content = fs.readFileSync(filePath, "utf-8");
+    const json = JSON.parse(content);
+
+    json.steps = steps;
+    json.updatedAt = new Date().toISOString();
+
+    fs.writeFileSync(filePath, JSON.stringify(json, null, 2));
+
+    // Update index entry if it exists
+    if (fs.existsSync(INDEX_FILE)) {
+      try {
+        const index = JSON.parse(fs.readFileSync(INDEX_FILE, "utf-8"));
+        const entry = index.find((t: any) => t.id === id);
+        if (entry) {
+          entry.stepsCount = json.steps.length;
+          entry.updatedAt = json.updatedAt;
+          fs.writeFileSync(INDEX_FILE, JSON.stringify(index, null, 2));
+        }
+      } catch {
+        /* ignore */
+      }
+    }
+
+    return NextResponse.json(steps);
+  } catch (error: any) {
+    return NextResponse.json({ error: error.message }, { status: 500 });
+  }
+}
@@ -72,3 +72,35 @@ export async function POST(request: Request) {
     );
   }
 }
+
+// `?name=<group>`; a group that still holds files is only removed with `&cascade=true`
+export async function DELETE(request: Request) {
+  try {
+    const { searchParams } = new URL(request.url);
+    const name = searchParams.get("name");
+    const cascade = searchParams.get("cascade") === "true";
+    if (!name || name.includes("/") || name.includes("\\") || name.startsWith(".")) {
+      return NextResponse.json({ error: "Invalid group name" }, { status: 400 });
+    }
+
+    const groupPath = path.join(TEST_DIR, name);
+    if (!fs.existsSync(groupPath)) {
+      return NextResponse.json({ error: "Test group not found" }, { status: 404 });
+    }
+    if (!cascade && fs.readdirSync(groupPath).length > 0) {
+      return NextResponse.json(
+        { error: "Test group is not empty" },
+        { status: 409 },
+      );
+    }
+
+    fs.rmSync(groupPath, { recursive: true, force: true });
+    return NextResponse.json({ name });
+  } catch (error) {
+    console.error(error);
+    return NextResponse.json(
+      { error: "Failed to delete group" },
+      { status: 500 },
+    );
+  }
+}
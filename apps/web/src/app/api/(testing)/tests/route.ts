@@ -55,6 +55,7 @@ export async function POST(request: Request) {
       folder: folderName,
       description: body.description || "",
       steps: body.steps || [],
+      ...(body.browser ? { browser: body.browser } : {}),
       updatedAt: new Date().toISOString(),
     };
 
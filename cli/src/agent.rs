@@ -0,0 +1,305 @@
+use crate::agent_browser::Browser;
+use crate::api;
+use crate::runner::{self, Output};
+use serde::Deserialize;
+use tokio::process::Command;
+
+// Runs `prompt` steps of local runs through an AI agent CLI. The agent gets the
+// instruction and the current page snapshot, drives the browser with
+// agent-browser if the instruction asks for it, and answers with a verdict.
+
+/// Agent CLIs that can run a prompt non-interactively. Names match the server's
+/// agent tools; the flags are the ones for a one-shot run, which differ from the
+/// flags the server uses to open an interactive session for some tools.
+pub struct AgentTool {
+    name: &'static str,
+    command: &'static str,
+    args: &'static [&'static str],
+    model_flag: &'static str,
+    /// Flag before the prompt; `None` when the prompt is a positional argument
+    prompt_flag: Option<&'static str>,
+}
+
+const AGENT_TOOLS: &[AgentTool] = &[
+    AgentTool { name: "claude", command: "claude", args: &[], model_flag: "--model", prompt_flag: Some("-p") },
+    AgentTool { name: "gemini", command: "gemini", args: &[], model_flag: "--model", prompt_flag: Some("-p") },
+    AgentTool { name: "codex", command: "codex", args: &["exec"], model_flag: "-m", prompt_flag: None },
+    AgentTool { name: "opencode", command: "opencode", args: &["run"], model_flag: "--model", prompt_flag: None },
+    AgentTool { name: "auggie", command: "auggie", args: &[], model_flag: "--model", prompt_flag: Some("--print") },
+    AgentTool { name: "cursor", command: "cursor-agent", args: &[], model_flag: "--model", prompt_flag: Some("-p") },
+    AgentTool { name: "kimi", command: "kimi", args: &[], model_flag: "--model", prompt_flag: Some("-p") },
+    AgentTool { name: "grok", command: "grok", args: &[], model_flag: "--model", prompt_flag: Some("-p") },
+    AgentTool { name: "aider", command: "aider", args: &["--yes"], model_flag: "--model", prompt_flag: Some("--message") },
+];
+
+/// Which agent runs prompt steps: the `agent` section of `run.json`, the
+/// `AGELUM_AGENT*` environment variables or the `test-run` flags.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Agent tool, as listed by `/api/agents?action=tools`
+    pub tool: Option<String>,
+    /// Model of the tool, as listed by `/api/agents?action=models`
+    pub model: Option<String>,
+    /// Shell command used instead of a tool. It reads the prompt on stdin and
+    /// prints the verdict, which makes it a deterministic stand-in for tests.
+    pub command: Option<String>,
+}
+
+impl AgentConfig {
+    pub fn from_env() -> Self {
+        AgentConfig {
+            tool: std::env::var("AGELUM_AGENT").ok().filter(|v| !v.is_empty()),
+            model: std::env::var("AGELUM_AGENT_MODEL").ok().filter(|v| !v.is_empty()),
+            command: std::env::var("AGELUM_AGENT_COMMAND").ok().filter(|v| !v.is_empty()),
+        }
+    }
+
+    /// This config if it names an agent, otherwise the fallback. A model on its
+    /// own applies to the fallback's tool.
+    pub fn or(self, fallback: AgentConfig) -> AgentConfig {
+        if self.tool.is_some() || self.command.is_some() {
+            return self;
+        }
+        AgentConfig { model: self.model.or(fallback.model), ..fallback }
+    }
+}
+
+pub enum Agent {
+    Tool { tool: &'static AgentTool, model: Option<String> },
+    Command(String),
+}
+
+impl std::fmt::Display for Agent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Agent::Tool { tool, model: Some(model) } => write!(f, "{} ({})", tool.name, model),
+            Agent::Tool { tool, model: None } => write!(f, "{}", tool.name),
+            Agent::Command(command) => write!(f, "$ {}", command),
+        }
+    }
+}
+
+/// The agent a config names, with its tool and model checked against the
+/// server's list. `None` when no agent is configured.
+pub async fn resolve(
+    client: &reqwest::Client,
+    url: &str,
+    config: AgentConfig,
+) -> anyhow::Result<Option<Agent>> {
+    if let Some(command) = config.command {
+        return Ok(Some(Agent::Command(command)));
+    }
+    let Some(name) = config.tool else {
+        return Ok(None);
+    };
+
+    let Some(tool) = AGENT_TOOLS.iter().find(|t| t.name == name) else {
+        let names: Vec<_> = AGENT_TOOLS.iter().map(|t| t.name).collect();
+        anyhow::bail!("Agent tool {} can't run prompt steps; use one of: {}", name, names.join(", "));
+    };
+    let models = api::fetch_agent_models(client, url, &name).await?;
+    if let Some(model) = &config.model {
+        // Tools without a model list accept any model
+        if !models.is_empty() && !models.contains(model) {
+            anyhow::bail!("Unknown model {} for {}; available: {}", model, name, models.join(", "));
+        }
+    }
+    Ok(Some(Agent::Tool { tool, model: config.model }))
+}
+
+/// Verdict the agent gives on a prompt step.
+#[derive(Deserialize, Debug)]
+struct Verdict {
+    pass: bool,
+    #[serde(default)]
+    reasoning: String,
+}
+
+/// Runs a prompt step: passes when the agent's verdict is a pass, and fails with
+/// its reasoning otherwise.
+pub async fn run_prompt(agent: &Agent, browser: &Browser, instruction: &str) -> anyhow::Result<Output> {
+    let snapshot = browser.snapshot().await?.text;
+    let page_url = browser.url().await.unwrap_or_default();
+    ask(agent, browser.session(), instruction, &page_url, &snapshot).await
+}
+
+/// Asks the agent about the page, as captured for the step.
+async fn ask(
+    agent: &Agent,
+    session: Option<&str>,
+    instruction: &str,
+    page_url: &str,
+    snapshot: &str,
+) -> anyhow::Result<Output> {
+    let prompt = build_prompt(instruction, page_url, snapshot);
+
+    let output = match agent {
+        Agent::Tool { tool, model } => {
+            let mut process = Command::new(tool.command);
+            process.args(tool.args);
+            if let Some(model) = model {
+                process.args([tool.model_flag, model]);
+            }
+            process.args(tool.prompt_flag).arg(&prompt);
+            // agent-browser commands the agent runs drive the test's session
+            process.envs(session.map(|s| ("AGENT_BROWSER_SESSION", s)));
+            run(process, tool.command, None).await?
+        }
+        Agent::Command(command) => {
+            let mut process = Command::new("sh");
+            process
+                .args(["-c", command])
+                .env("AGELUM_PROMPT_INSTRUCTION", instruction)
+                .env("AGELUM_PAGE_URL", page_url)
+                .env("AGELUM_PAGE_SNAPSHOT", snapshot)
+                .envs(session.map(|s| ("AGENT_BROWSER_SESSION", s)));
+            run(process, command, Some(&prompt)).await?
+        }
+    };
+
+    let verdict = parse_verdict(&output.stdout).ok_or_else(|| {
+        anyhow::anyhow!(
+            "The agent gave no verdict; expected a {{\"pass\": ..., \"reasoning\": ...}} line, got:\n{}",
+            output.stdout.trim()
+        )
+    })?;
+    if !verdict.pass {
+        anyhow::bail!("Prompt check failed: {}", verdict.reasoning);
+    }
+    Ok(Output {
+        stdout: format!("✓ {}", verdict.reasoning),
+        ..output
+    })
+}
+
+async fn run(process: Command, command: &str, input: Option<&str>) -> anyhow::Result<Output> {
+    match runner::capture(process, command, input).await {
+        Ok(result) => result,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("'{}' command not found; install the agent or choose another one", command)
+        }
+        Err(e) => anyhow::bail!("Failed to run {}: {}", command, e),
+    }
+}
+
+fn build_prompt(instruction: &str, page_url: &str, snapshot: &str) -> String {
+    format!(
+        "You are running one step of an automated browser test.\n\
+         \n\
+         Step: {instruction}\n\
+         \n\
+         The page is open in agent-browser. If the step asks for actions, perform them \
+         with agent-browser commands (e.g. `agent-browser click @e2`), then check the result.\n\
+         \n\
+         Current URL: {page_url}\n\
+         Accessibility snapshot of the current page:\n\
+         {snapshot}\n\
+         \n\
+         Finish your answer with one line of JSON saying whether the step succeeded:\n\
+         {{\"pass\": true, \"reasoning\": \"<one sentence>\"}}\n"
+    )
+}
+
+/// Finds the verdict in the agent's output: the last line that is a JSON
+/// verdict, or else the last line starting with the word PASS or FAIL.
+fn parse_verdict(output: &str) -> Option<Verdict> {
+    let lines: Vec<&str> = output.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if let Some(verdict) = lines.iter().rev().find_map(|line| serde_json::from_str(line).ok()) {
+        return Some(verdict);
+    }
+    // Agents sometimes pretty-print the JSON
+    if let Ok(verdict) = serde_json::from_str(output.trim()) {
+        return Some(verdict);
+    }
+
+    lines.iter().rev().find_map(|line| {
+        let upper = line.to_ascii_uppercase();
+        let pass = if upper.starts_with("PASS") {
+            true
+        } else if upper.starts_with("FAIL") {
+            false
+        } else {
+            return None;
+        };
+        // The whole word only, so "Password ..." or "Failing over ..." are not verdicts
+        let rest = &line[4..];
+        if !(rest.is_empty() || rest.starts_with([':', '-']) || rest.starts_with(char::is_whitespace)) {
+            return None;
+        }
+        let reasoning = rest.trim_start_matches([':', '-', ' ']).trim();
+        Some(Verdict { pass, reasoning: reasoning.to_string() })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(output: &str) -> Option<(bool, String)> {
+        parse_verdict(output).map(|v| (v.pass, v.reasoning))
+    }
+
+    #[test]
+    fn reads_the_last_json_verdict() {
+        let output = "Checking the page...\n{\"pass\": false, \"reasoning\": \"draft\"}\nDone.\n{\"pass\": true, \"reasoning\": \"The banner is shown\"}\n";
+        assert_eq!(verdict(output), Some((true, "The banner is shown".to_string())));
+        assert_eq!(verdict("{\"pass\": false}"), Some((false, String::new())));
+    }
+
+    #[test]
+    fn reads_a_pretty_printed_verdict() {
+        let output = "{\n  \"pass\": false,\n  \"reasoning\": \"No banner\"\n}\n";
+        assert_eq!(verdict(output), Some((false, "No banner".to_string())));
+    }
+
+    #[test]
+    fn reads_pass_and_fail_lines() {
+        assert_eq!(verdict("Looking...\nPASS: the banner is shown"), Some((true, "the banner is shown".to_string())));
+        assert_eq!(verdict("fail - no banner\n"), Some((false, "no banner".to_string())));
+        assert_eq!(verdict("PASS"), Some((true, String::new())));
+        assert_eq!(verdict("FAIL-timeout"), Some((false, "timeout".to_string())));
+        assert_eq!(verdict("Pass\tall good"), Some((true, "all good".to_string())));
+    }
+
+    #[test]
+    fn words_starting_with_pass_or_fail_are_not_verdicts() {
+        assert!(verdict("Password field is empty").is_none());
+        assert!(verdict("Failing over to the backup page").is_none());
+        assert!(verdict("PASSED").is_none());
+        // An earlier verdict line still counts
+        assert_eq!(verdict("FAIL: no banner\nPassword field is empty"), Some((false, "no banner".to_string())));
+    }
+
+    #[test]
+    fn malformed_output_has_no_verdict() {
+        assert!(verdict("").is_none());
+        assert!(verdict("The page looks fine to me").is_none());
+        assert!(verdict("{\"result\": \"ok\"}").is_none());
+        assert!(verdict("{\"pass\": \"yes\"}").is_none());
+    }
+
+    #[tokio::test]
+    async fn runs_the_step_through_a_command() {
+        // Passes when the snapshot it gets shows the text the step asks for
+        let stub = Agent::Command(
+            r#"cat > /dev/null
+               case "$AGELUM_PAGE_SNAPSHOT" in
+                 *"$AGELUM_PROMPT_INSTRUCTION"*) echo '{"pass": true, "reasoning": "found it"}' ;;
+                 *) echo "FAIL: not on $AGELUM_PAGE_URL" ;;
+               esac"#
+                .to_string(),
+        );
+        let snapshot = "- heading \"Welcome back\" [ref=e1]";
+
+        let output = ask(&stub, None, "Welcome back", "http://app/home", snapshot).await.unwrap();
+        assert_eq!(output.stdout, "✓ found it");
+
+        let error = ask(&stub, None, "Goodbye", "http://app/home", snapshot).await.unwrap_err();
+        assert_eq!(error.to_string(), "Prompt check failed: not on http://app/home");
+
+        let silent = Agent::Command("cat > /dev/null; echo thinking".to_string());
+        let error = ask(&silent, None, "Welcome back", "http://app/home", snapshot).await.unwrap_err();
+        assert!(error.to_string().starts_with("The agent gave no verdict"), "{}", error);
+    }
+}
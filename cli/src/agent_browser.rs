@@ -0,0 +1,341 @@
+use crate::runner::{self, CommandFailed, Output};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+// Typed calls to agent-browser. Commands run with `--json`, so failures are read
+// from the response instead of the exit status alone.
+
+/// Browser settings of a test, in the `browser` section of its `test.json`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct BrowserOptions {
+    /// agent-browser session, so tests can run side by side in separate browsers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Show the browser window instead of running headless
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewport: Option<Viewport>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Response of a command run with `--json`.
+#[derive(Deserialize, Debug)]
+struct Response {
+    success: bool,
+    #[serde(default)]
+    data: Value,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Page snapshot: the accessibility tree as text, and the elements it refers to.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub text: String,
+    pub refs: BTreeMap<String, Element>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Element {
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Snapshot {
+    /// Elements by ref. Taken from the text when agent-browser returned no refs.
+    pub fn elements(&self) -> BTreeMap<String, Element> {
+        if !self.refs.is_empty() {
+            return self.refs.clone();
+        }
+        self.text
+            .lines()
+            .filter_map(|line| {
+                let (role, name, reference) = parse_snapshot_line(line);
+                Some((reference?, Element { role, name }))
+            })
+            .collect()
+    }
+}
+
+/// Splits a snapshot line such as `- button "Sign in" [ref=e2]` into role,
+/// quoted name and ref.
+fn parse_snapshot_line(line: &str) -> (String, Option<String>, Option<String>) {
+    let line = line.trim_start().trim_start_matches("- ");
+    let role = line
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let name = line
+        .split_once('"')
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(name, _)| name.to_string());
+    let reference = line
+        .split_once("[ref=")
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(reference, _)| reference.to_string());
+    (role, name, reference)
+}
+
+/// A browser session driven through agent-browser.
+#[derive(Debug, Default, Clone)]
+pub struct Browser {
+    options: BrowserOptions,
+}
+
+impl Browser {
+    pub fn new(options: BrowserOptions) -> Self {
+        Browser { options }
+    }
+
+    pub fn session(&self) -> Option<&str> {
+        self.options.session.as_deref()
+    }
+
+    /// Applies the configured viewport, starting the browser if needed.
+    pub async fn prepare(&self) -> anyhow::Result<()> {
+        if let Some(viewport) = self.options.viewport {
+            self.call(&["set", "viewport", &viewport.width.to_string(), &viewport.height.to_string()])
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        let data = self.call(&["snapshot"]).await?;
+        Ok(Snapshot {
+            text: field(&data, "snapshot").unwrap_or_default(),
+            refs: data
+                .get("refs")
+                .and_then(|refs| serde_json::from_value(refs.clone()).ok())
+                .unwrap_or_default(),
+        })
+    }
+
+    pub async fn url(&self) -> anyhow::Result<String> {
+        let data = self.call(&["get", "url"]).await?;
+        Ok(field(&data, "url").unwrap_or_default())
+    }
+
+    pub async fn title(&self) -> anyhow::Result<String> {
+        let data = self.call(&["get", "title"]).await?;
+        Ok(field(&data, "title").unwrap_or_default())
+    }
+
+    /// Runs a command line of a test step, with a readable message of the
+    /// response as its stdout. The process's stderr and exit code are kept.
+    pub async fn run(&self, args: &[String]) -> anyhow::Result<Output> {
+        let words: Vec<&str> = args.iter().map(String::as_str).collect();
+        let (data, output) = self.call_captured(&words).await?;
+        let message = match words.as_slice() {
+            ["open", url] => format!("Opened {}", field(&data, "url").as_deref().unwrap_or(url)),
+            ["click", selector] => format!("Clicked {}", selector),
+            ["fill", selector, text] => format!("Filled {} with {:?}", selector, text),
+            ["snapshot"] => field(&data, "snapshot").unwrap_or_default(),
+            ["screenshot"] => format!("Saved {}", screenshot_path(&data, None)?.display()),
+            ["screenshot", path] => format!("Saved {}", screenshot_path(&data, Some(path))?.display()),
+            ["eval", _] => match eval_result(data) {
+                Value::String(s) => s,
+                other => other.to_string(),
+            },
+            _ => display(&data),
+        };
+        Ok(Output { stdout: message, ..output })
+    }
+
+    /// Runs a command with `--json` and returns the response data, failing when
+    /// the command reports a failure.
+    async fn call(&self, args: &[&str]) -> anyhow::Result<Value> {
+        Ok(self.call_captured(args).await?.0)
+    }
+
+    /// Runs a command with `--json` and returns the response data and what the
+    /// process printed.
+    async fn call_captured(&self, args: &[&str]) -> anyhow::Result<(Value, Output)> {
+        let mut process = Command::new("agent-browser");
+        process.args(self.global_args()).arg("--json").args(args);
+        let label = format!("agent-browser {}", args.join(" "));
+        let result = match runner::capture(process, &label, None).await {
+            Ok(result) => result,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("'agent-browser' command not found. Install it with: npm install -g agent-browser")
+            }
+            Err(e) => anyhow::bail!("Failed to execute agent-browser: {}", e),
+        };
+        read_response(&label, result)
+    }
+
+    fn global_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(session) = &self.options.session {
+            args.extend(["--session".to_string(), session.clone()]);
+        }
+        if self.options.headed == Some(true) {
+            args.push("--headed".to_string());
+        }
+        args
+    }
+}
+
+/// Response data of a finished command. A `--json` response with `success: false`
+/// fails like a failure exit status, keeping the output for the step log.
+fn read_response(label: &str, result: anyhow::Result<Output>) -> anyhow::Result<(Value, Output)> {
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => {
+            return Err(match e.downcast::<CommandFailed>() {
+                Ok(mut failed) => {
+                    failed.reason = serde_json::from_str::<Response>(failed.output.stdout.trim())
+                        .ok()
+                        .and_then(|response| response.error);
+                    anyhow::Error::new(failed)
+                }
+                Err(e) => e,
+            });
+        }
+    };
+
+    // Older agent-browser versions print plain text
+    let Ok(response) = serde_json::from_str::<Response>(output.stdout.trim()) else {
+        return Ok((Value::String(output.stdout.clone()), output));
+    };
+    if !response.success {
+        return Err(anyhow::Error::new(CommandFailed {
+            command: label.to_string(),
+            reason: Some(response.error.unwrap_or_else(|| "no error message".to_string())),
+            output,
+        }));
+    }
+    Ok((response.data, output))
+}
+
+fn screenshot_path(data: &Value, path: Option<&str>) -> anyhow::Result<PathBuf> {
+    field(data, "path")
+        .or(path.map(String::from))
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow::anyhow!("agent-browser screenshot returned no path"))
+}
+
+fn eval_result(data: Value) -> Value {
+    match data {
+        Value::Object(mut map) if map.contains_key("result") => map.remove("result").unwrap_or_default(),
+        other => other,
+    }
+}
+
+/// A string field of the response data, or the data itself when it is a string.
+fn field(data: &Value, key: &str) -> Option<String> {
+    match data {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Object(map) => map.get(key).and_then(Value::as_str).map(String::from),
+        _ => None,
+    }
+}
+
+/// Response data as printed in the run output.
+fn display(data: &Value) -> String {
+    match data {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Object(map) if map.len() == 1 => match map.values().next() {
+            Some(Value::String(s)) => s.clone(),
+            _ => data.to_string(),
+        },
+        _ => data.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn printed(stdout: &str, stderr: &str, exit_code: i32) -> Output {
+        Output { stdout: stdout.to_string(), stderr: stderr.to_string(), exit_code: Some(exit_code) }
+    }
+
+    #[test]
+    fn reads_the_response_data() {
+        let (data, output) = read_response(
+            "agent-browser get url",
+            Ok(printed(r#"{"success": true, "data": {"url": "https://example.com/"}}"#, "debug: warm", 0)),
+        )
+        .unwrap();
+        assert_eq!(data, json!({"url": "https://example.com/"}));
+        assert_eq!((output.stderr.as_str(), output.exit_code), ("debug: warm", Some(0)));
+
+        // Plain text from older versions is the data itself
+        let (data, _) = read_response("agent-browser get url", Ok(printed("https://example.com/\n", "", 0))).unwrap();
+        assert_eq!(field(&data, "url").as_deref(), Some("https://example.com/"));
+    }
+
+    #[test]
+    fn reported_failures_keep_the_output() {
+        let e = read_response(
+            "agent-browser click @e9",
+            Ok(printed(r#"{"success": false, "error": "Element @e9 not found"}"#, "trace", 0)),
+        )
+        .unwrap_err();
+        assert_eq!(e.to_string(), "agent-browser click @e9 failed: Element @e9 not found");
+        let failed = e.downcast::<CommandFailed>().unwrap();
+        assert_eq!((failed.output.stderr.as_str(), failed.output.exit_code), ("trace", Some(0)));
+
+        // A failure exit status gets the reason from the JSON output too
+        let exited = CommandFailed {
+            command: "agent-browser open x".to_string(),
+            reason: None,
+            output: printed(r#"{"success": false, "error": "Invalid URL"}"#, "", 1),
+        };
+        let e = read_response("agent-browser open x", Err(anyhow::Error::new(exited))).unwrap_err();
+        assert_eq!(e.to_string(), "agent-browser open x failed (exit status 1): Invalid URL");
+        assert_eq!(e.downcast::<CommandFailed>().unwrap().output.exit_code, Some(1));
+    }
+
+    #[test]
+    fn parses_snapshot_lines() {
+        assert_eq!(
+            parse_snapshot_line(r#"  - button "Sign in" [ref=e2]"#),
+            ("button".to_string(), Some("Sign in".to_string()), Some("e2".to_string()))
+        );
+        assert_eq!(
+            parse_snapshot_line("- heading: Welcome [ref=e1]"),
+            ("heading".to_string(), None, Some("e1".to_string()))
+        );
+        assert_eq!(parse_snapshot_line("- text"), ("text".to_string(), None, None));
+
+        let snapshot = Snapshot {
+            text: "- main\n  - link \"Docs\" [ref=e3]\n  - textbox [ref=e4]".to_string(),
+            refs: BTreeMap::new(),
+        };
+        let elements = snapshot.elements();
+        assert_eq!(elements.keys().collect::<Vec<_>>(), ["e3", "e4"]);
+        assert_eq!(elements["e3"].name.as_deref(), Some("Docs"));
+        assert_eq!(elements["e4"].role, "textbox");
+    }
+
+    #[test]
+    fn reads_fields_and_displays_data() {
+        assert_eq!(field(&json!({"title": "Home"}), "title").as_deref(), Some("Home"));
+        assert_eq!(field(&json!(" Home \n"), "title").as_deref(), Some("Home"));
+        assert_eq!(field(&json!({"title": 3}), "title"), None);
+        assert_eq!(field(&json!([1]), "title"), None);
+
+        assert_eq!(display(&Value::Null), "");
+        assert_eq!(display(&json!("text")), "text");
+        assert_eq!(display(&json!({"visible": "yes"})), "yes");
+        assert_eq!(display(&json!({"visible": true})), r#"{"visible":true}"#);
+        assert_eq!(display(&json!({"a": "1", "b": "2"})), r#"{"a":"1","b":"2"}"#);
+    }
+}
@@ -0,0 +1,634 @@
+use crate::types::{
+    AIBackend, AIRecommendation, AgentModelsResponse, ApiKey, AppStatus, CreatedApiKey, ServerVersion, SettingsResponse, ExecutionLog, ExecutionResult, FilesResponse, RecordCapture,
+    RecordExecuteResponse, Repository, RepositoryResponse, Task, TaskResponse, Test, TestExecution, TestStep,
+};
+use crate::config;
+use crate::http::Dispatch;
+use std::path::PathBuf;
+
+// Helpers for commands that need structured data back from the API instead of
+// printing the response directly.
+
+async fn check(resp: reqwest::Response, what: &str) -> anyhow::Result<reqwest::Response> {
+    if resp.status().is_success() {
+        return Ok(resp);
+    }
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+    anyhow::bail!("Request for {} failed: {} {}", what, status, text.trim())
+}
+
+/// The repositories as the server lists them, with its base path and mode.
+pub async fn fetch_repository_response(client: &reqwest::Client, url: &str) -> anyhow::Result<RepositoryResponse> {
+    let resp = client.get(format!("{}/api/repositories", url)).dispatch().await?;
+    Ok(check(resp, "repositories").await?.json().await?)
+}
+
+pub async fn fetch_repositories(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<Repository>> {
+    let resp_json = fetch_repository_response(client, url).await?;
+    let base_path = resp_json.base_path;
+    let server_tests_dir = resp_json.tests_dir;
+    let runs_here = runs_here(url);
+    Ok(resp_json
+        .repositories
+        .into_iter()
+        .map(|mut repo| {
+            let mapped = config::local_repository_path(&repo.path, &base_path);
+            let local = runs_here || mapped.is_some();
+            if let Some(mapped) = mapped {
+                repo.path = mapped.to_string_lossy().into_owned();
+            }
+            repo.tests_dir = local_tests_dir(server_tests_dir.as_deref(), &repo.path, local, runs_here, &base_path);
+            repo
+        })
+        .collect())
+}
+
+/// The server's tests folder as a folder of this machine, if it can be reached.
+/// `repo_path` is the repository as seen from here, and `local` whether it is a
+/// folder of this machine.
+fn local_tests_dir(
+    server_dir: Option<&str>,
+    repo_path: &str,
+    local: bool,
+    runs_here: bool,
+    base_path: &str,
+) -> Option<PathBuf> {
+    match server_dir {
+        Some(dir) if runs_here => Some(PathBuf::from(dir)),
+        Some(dir) => config::local_repository_path(dir, base_path),
+        // Older servers don't report it; assume the tests are kept in the repository
+        None => local.then(|| PathBuf::from(repo_path).join(".agelum/tests")),
+    }
+}
+
+/// Whether the server at `url` runs on this machine, so that its paths are
+/// paths of this machine.
+fn runs_here(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    // IPv6 hosts come in brackets
+    let host = url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    host == "localhost" || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+pub async fn fetch_repository(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+) -> anyhow::Result<Repository> {
+    fetch_repositories(client, url)
+        .await?
+        .into_iter()
+        .find(|r| r.name == repo)
+        .ok_or_else(|| anyhow::anyhow!("Repository not found: {}", repo))
+}
+
+pub async fn fetch_tasks(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+) -> anyhow::Result<Vec<Task>> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .get(format!("{}/api/tasks?repo={}", url, encoded_repo))
+        .dispatch()
+        .await?;
+    let resp_json: TaskResponse = check(resp, "tasks").await?.json().await?;
+    Ok(resp_json.tasks)
+}
+
+/// Moves a task to another state directory.
+pub async fn move_task(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    task_id: &str,
+    from_state: &str,
+    to_state: &str,
+) -> anyhow::Result<()> {
+    let body = serde_json::json!({
+        "repo": repo,
+        "action": "move",
+        "taskId": task_id,
+        "fromState": from_state,
+        "toState": to_state
+    });
+    let resp = client.post(format!("{}/api/tasks", url)).json(&body).dispatch().await?;
+    check(resp, "moving task").await?;
+    Ok(())
+}
+
+/// Tree of the files under `.agelum/<sub_path>` of a repository.
+pub async fn fetch_files(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    sub_path: &str,
+) -> anyhow::Result<FilesResponse> {
+    let resp = client
+        .get(format!(
+            "{}/api/files?repo={}&path={}",
+            url,
+            urlencoding::encode(repo),
+            urlencoding::encode(sub_path)
+        ))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "files").await?.json().await?)
+}
+
+/// Content of a file, read by the server.
+pub async fn read_file(client: &reqwest::Client, url: &str, path: &str) -> anyhow::Result<String> {
+    let resp = client
+        .get(format!("{}/api/file?path={}", url, urlencoding::encode(path)))
+        .dispatch()
+        .await?;
+    let json: serde_json::Value = check(resp, "file").await?.json().await?;
+    json.get("content")
+        .and_then(|c| c.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("No content returned for {}", path))
+}
+
+pub async fn fetch_tests(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+) -> anyhow::Result<Vec<Test>> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .get(format!("{}/api/tests?repo={}", url, encoded_repo))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "tests").await?.json().await?)
+}
+
+/// Names of the test groups, the folders under the tests folder.
+pub async fn fetch_test_groups(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+) -> anyhow::Result<Vec<String>> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .get(format!("{}/api/tests/groups?repo={}", url, encoded_repo))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "test groups").await?.json().await?)
+}
+
+pub async fn fetch_test(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<Test> {
+    fetch_tests(client, url, repo)
+        .await?
+        .into_iter()
+        .find(|t| t.id == test_id)
+        .ok_or_else(|| anyhow::anyhow!("Test not found: {}", test_id))
+}
+
+/// Full test definition including its steps, as stored in the test's `test.json`.
+pub async fn fetch_test_definition(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<Test> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .get(format!("{}/api/tests/{}?repo={}", url, test_id, encoded_repo))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "test").await?.json().await?)
+}
+
+/// Like [`fetch_test_definition`], but `None` for a test of the index that has
+/// no `test.json`.
+pub async fn find_test_definition(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<Option<Test>> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .get(format!("{}/api/tests/{}?repo={}", url, test_id, encoded_repo))
+        .dispatch()
+        .await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(check(resp, "test").await?.json().await?))
+}
+
+/// Creates a test from a draft, with its steps and browser settings. The server
+/// assigns a new id, ignoring the draft's, and derives the folder from the name
+/// when the draft has none.
+pub async fn create_test(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    draft: &Test,
+) -> anyhow::Result<Test> {
+    let encoded_repo = urlencoding::encode(repo);
+    let mut body = serde_json::to_value(draft)?;
+    if let Some(body) = body.as_object_mut() {
+        body.remove("id");
+    }
+    let resp = client
+        .post(format!("{}/api/tests?repo={}", url, encoded_repo))
+        .json(&body)
+        .dispatch()
+        .await?;
+    Ok(check(resp, "test").await?.json().await?)
+}
+
+/// Creates or replaces a test under its own id, including group, folder and steps.
+pub async fn save_test(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test: &Test,
+) -> anyhow::Result<()> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .post(format!("{}/api/tests?repo={}", url, encoded_repo))
+        .json(test)
+        .dispatch()
+        .await?;
+    check(resp, "test").await?;
+    Ok(())
+}
+
+pub async fn delete_test(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<()> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .delete(format!("{}/api/tests/{}?repo={}", url, test_id, encoded_repo))
+        .dispatch()
+        .await?;
+    check(resp, "test").await?;
+    Ok(())
+}
+
+/// Group the server puts a test in when it is created without one.
+pub const DEFAULT_TEST_GROUP: &str = "EXPERIMENTAL";
+
+/// Folder name the server derives from a test name.
+pub fn test_folder_name(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
+}
+
+/// Replaces the whole step list of a test. The rest of its definition (group,
+/// folder, description) is kept.
+pub async fn save_test_steps(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    steps: &[TestStep],
+) -> anyhow::Result<()> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .put(format!("{}/api/tests/{}/steps?repo={}", url, test_id, encoded_repo))
+        .json(steps)
+        .dispatch()
+        .await?;
+    check(resp, "test steps").await?;
+    Ok(())
+}
+
+/// Deletes the folder of a test group; with `cascade`, also what is left in
+/// it. `false` when there is no such group.
+pub async fn delete_test_group(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    name: &str,
+    cascade: bool,
+) -> anyhow::Result<bool> {
+    let resp = client
+        .delete(format!(
+            "{}/api/tests/groups?repo={}&name={}&cascade={}",
+            url,
+            urlencoding::encode(repo),
+            urlencoding::encode(name),
+            cascade
+        ))
+        .dispatch()
+        .await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+    check(resp, "test group").await?;
+    Ok(true)
+}
+
+/// Executions of a test, most recent first.
+pub async fn fetch_executions(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<Vec<TestExecution>> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .get(format!("{}/api/tests/{}/executions?repo={}", url, test_id, encoded_repo))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "test executions").await?.json().await?)
+}
+
+/// Records the result of a run, e.g. one made by the local runner.
+pub async fn finish_execution(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    result: &ExecutionResult,
+) -> anyhow::Result<()> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client
+        .post(format!("{}/api/tests/{}/finish?repo={}", url, test_id, encoded_repo))
+        .json(result)
+        .dispatch()
+        .await?;
+    check(resp, "test finish").await?;
+    Ok(())
+}
+
+/// Finds an execution by id, or the most recent one for "latest".
+pub fn find_execution<'a>(executions: &'a [TestExecution], id: &str) -> anyhow::Result<&'a TestExecution> {
+    if id == "latest" {
+        return executions
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No executions found"));
+    }
+    executions
+        .iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| anyhow::anyhow!("Execution not found: {}", id))
+}
+
+/// Downloads an execution artifact. Artifacts are usually served from
+/// `/api/tests/artifacts/...`, but older results may hold absolute URLs or local paths.
+pub async fn fetch_artifact(
+    client: &reqwest::Client,
+    url: &str,
+    artifact: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let request_url = if artifact.starts_with("http://") || artifact.starts_with("https://") {
+        artifact.to_string()
+    } else if artifact.starts_with("/api/") {
+        format!("{}{}", url, artifact)
+    } else {
+        return Ok(std::fs::read(artifact)?);
+    };
+
+    let resp = client.get(request_url).dispatch().await?;
+    Ok(check(resp, artifact).await?.bytes().await?.to_vec())
+}
+
+/// Runs one agent-browser command in the server's recording browser.
+pub async fn record_execute(
+    client: &reqwest::Client,
+    url: &str,
+    command: &str,
+    args: &[String],
+) -> anyhow::Result<RecordExecuteResponse> {
+    let body = serde_json::json!({
+        "command": command,
+        "args": args,
+    });
+    let resp = client
+        .post(format!("{}/api/tests/record/execute", url))
+        .json(&body)
+        .dispatch()
+        .await?;
+    Ok(check(resp, "record execute").await?.json().await?)
+}
+
+/// Screenshot and accessibility snapshot of the recording browser.
+pub async fn record_capture(client: &reqwest::Client, url: &str) -> anyhow::Result<RecordCapture> {
+    let resp = client
+        .post(format!("{}/api/tests/record/capture", url))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "record capture").await?.json().await?)
+}
+
+pub async fn record_backends(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<AIBackend>> {
+    let resp = client.get(format!("{}/api/tests/record/ai", url)).dispatch().await?;
+    Ok(check(resp, "AI backends").await?.json().await?)
+}
+
+/// Models the agent tool can run with. Fails for tools the server doesn't know.
+pub async fn fetch_agent_models(
+    client: &reqwest::Client,
+    url: &str,
+    tool: &str,
+) -> anyhow::Result<Vec<String>> {
+    let resp = client
+        .get(format!("{}/api/agents?action=models&tool={}", url, urlencoding::encode(tool)))
+        .dispatch()
+        .await?;
+    let resp_json: AgentModelsResponse = check(resp, "agent models").await?.json().await?;
+    Ok(resp_json.models)
+}
+
+/// Asks the recording AI to turn a natural-language instruction into a step.
+pub async fn record_ai(
+    client: &reqwest::Client,
+    url: &str,
+    backend: &str,
+    prompt: &str,
+    capture: &RecordCapture,
+    project_path: Option<&str>,
+) -> anyhow::Result<AIRecommendation> {
+    let body = serde_json::json!({
+        "screenshot": capture.screenshot,
+        "snapshot": capture.snapshot,
+        "prompt": prompt,
+        "deterministic": true,
+        "backend": backend,
+        "projectPath": project_path,
+    });
+    let resp = client
+        .post(format!("{}/api/tests/record/ai", url))
+        .json(&body)
+        .dispatch()
+        .await?;
+    Ok(check(resp, "AI recommendation").await?.json().await?)
+}
+
+pub async fn fetch_api_keys(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<ApiKey>> {
+    let resp = client.get(format!("{}/api/api-keys", url)).dispatch().await?;
+    Ok(check(resp, "API keys").await?.json().await?)
+}
+
+pub async fn create_api_key(client: &reqwest::Client, url: &str, name: &str) -> anyhow::Result<CreatedApiKey> {
+    let resp = client
+        .post(format!("{}/api/api-keys", url))
+        .json(&serde_json::json!({ "name": name }))
+        .dispatch()
+        .await?;
+    Ok(check(resp, "new API key").await?.json().await?)
+}
+
+pub async fn revoke_api_key(client: &reqwest::Client, url: &str, id: &str) -> anyhow::Result<()> {
+    let resp = client
+        .delete(format!("{}/api/api-keys", url))
+        .json(&serde_json::json!({ "id": id }))
+        .dispatch()
+        .await?;
+    check(resp, "API key revocation").await?;
+    Ok(())
+}
+
+pub async fn fetch_settings(
+    client: &reqwest::Client,
+    url: &str,
+) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let resp = client.get(format!("{}/api/settings", url)).dispatch().await?;
+    let resp_json: SettingsResponse = check(resp, "settings").await?.json().await?;
+    Ok(resp_json.settings)
+}
+
+/// Merges `changes` into the stored settings; the other keys are left alone.
+pub async fn update_settings(
+    client: &reqwest::Client,
+    url: &str,
+    changes: &serde_json::Map<String, serde_json::Value>,
+) -> anyhow::Result<()> {
+    let resp = client
+        .patch(format!("{}/api/settings", url))
+        .json(&serde_json::json!({ "settings": changes }))
+        .dispatch()
+        .await?;
+    check(resp, "settings update").await?;
+    Ok(())
+}
+
+/// Local folder holding the tests of a repository, as the server reports it:
+/// `.agelum/tests` under its working directory. `None` when it is on another
+/// machine and not mapped with `local_path`.
+pub fn tests_dir(repo: &Repository) -> Option<PathBuf> {
+    repo.tests_dir.clone()
+}
+
+/// Local folder of a test: `.agelum/tests/<Group>/<Folder>`.
+pub fn test_dir(repo: &Repository, test: &Test) -> anyhow::Result<PathBuf> {
+    let (Some(group), Some(folder)) = (&test.group, &test.folder) else {
+        anyhow::bail!("Test {} has no group/folder in the test index", test.id);
+    };
+    let Some(tests_dir) = tests_dir(repo) else {
+        anyhow::bail!(
+            "The tests of {} are on another machine; set local_path in the profile to use their folders",
+            repo.name
+        );
+    };
+    Ok(tests_dir.join(group).join(folder))
+}
+
+/// Local folder holding the execution runs of a test: `.agelum/tests/runs/<test-id>`.
+pub fn runs_dir(repo: &Repository, test_id: &str) -> Option<PathBuf> {
+    Some(tests_dir(repo)?.join("runs").join(test_id))
+}
+
+/// Step log of a local run: `.agelum/tests/runs/<test-id>/<execution-id>/log.json`.
+pub fn execution_log_path(repo: &Repository, test_id: &str, execution_id: &str) -> Option<PathBuf> {
+    Some(runs_dir(repo, test_id)?.join(execution_id).join("log.json"))
+}
+
+pub fn read_execution_log(repo: &Repository, test_id: &str, execution_id: &str) -> anyhow::Result<ExecutionLog> {
+    let Some(path) = execution_log_path(repo, test_id, execution_id) else {
+        anyhow::bail!(
+            "Repository {} is on another machine; set local_path in the profile to read the step logs of its runs",
+            repo.name
+        );
+    };
+    if !path.exists() {
+        anyhow::bail!(
+            "Execution {} has no step log; only local runs (`test-run --local`) record one",
+            execution_id
+        );
+    }
+    serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+/// The server's version and routes; `None` for servers that predate
+/// `/api/version`. Not retried, as it's checked before other requests.
+pub async fn fetch_version(client: &reqwest::Client, url: &str) -> anyhow::Result<Option<ServerVersion>> {
+    let resp = client.get(format!("{}/api/version", url)).dispatch_once().await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(check(resp, "the server version").await?.json().await?))
+}
+
+/// Whether the app of the repository runs; `None` when the repository is not a
+/// project of the user settings, whose apps Agelum doesn't run.
+pub async fn fetch_app_status(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<Option<AppStatus>> {
+    let encoded_repo = urlencoding::encode(repo);
+    let resp = client.get(format!("{}/api/app-status?repo={}", url, encoded_repo)).dispatch().await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(check(resp, "app status").await?.json().await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_the_tests_folder_the_server_reports() {
+        let server = Some("/srv/agelum/apps/web/.agelum/tests");
+        assert_eq!(
+            local_tests_dir(server, "/srv/code/demo", true, true, "/srv/code"),
+            Some(PathBuf::from("/srv/agelum/apps/web/.agelum/tests"))
+        );
+        // On another machine, and no local_path mapping it
+        assert_eq!(local_tests_dir(server, "/srv/code/demo", false, false, "/srv/code"), None);
+        // Older servers keep them in the repository, as far as the CLI can tell
+        assert_eq!(
+            local_tests_dir(None, "/home/me/code/demo", true, false, "/srv/code"),
+            Some(PathBuf::from("/home/me/code/demo/.agelum/tests"))
+        );
+        assert_eq!(local_tests_dir(None, "/srv/code/demo", false, false, "/srv/code"), None);
+    }
+
+    #[test]
+    fn test_folders_need_the_tests_on_this_machine() {
+        let test: Test = serde_json::from_str(r#"{"id": "t1", "name": "Login", "group": "LOGIN", "folder": "Login"}"#).unwrap();
+        let mut repo = Repository { name: "demo".to_string(), path: "/srv/code/demo".to_string(), tests_dir: None };
+        assert!(test_dir(&repo, &test).unwrap_err().to_string().contains("set local_path"));
+        assert_eq!(runs_dir(&repo, "t1"), None);
+
+        repo.tests_dir = Some(PathBuf::from("/home/me/web/.agelum/tests"));
+        assert_eq!(test_dir(&repo, &test).unwrap(), PathBuf::from("/home/me/web/.agelum/tests/LOGIN/Login"));
+        assert_eq!(
+            execution_log_path(&repo, "t1", "exec-1"),
+            Some(PathBuf::from("/home/me/web/.agelum/tests/runs/t1/exec-1/log.json"))
+        );
+    }
+
+    #[test]
+    fn recognizes_servers_on_this_machine() {
+        assert!(runs_here("http://localhost:6500"));
+        assert!(runs_here("http://127.0.0.1:6500/"));
+        assert!(runs_here("http://[::1]:6500"));
+        assert!(!runs_here("https://agelum.example.com"));
+        assert!(!runs_here("not a url"));
+    }
+}
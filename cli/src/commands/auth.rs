@@ -0,0 +1,269 @@
+use crate::config::{self, Settings, TokenSource};
+use crate::credentials::{self, Credential, CredentialKind, CredentialStore};
+use crate::http::Dispatch;
+use crate::interactive;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How long the browser has to come back with the token.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Provider {
+    Github,
+    Google,
+    Yandex,
+}
+
+impl Provider {
+    fn as_str(self) -> &'static str {
+        match self {
+            Provider::Github => "github",
+            Provider::Google => "google",
+            Provider::Yandex => "yandex",
+        }
+    }
+}
+
+/// Signs in through the server's gateway: the browser goes through the OAuth
+/// provider and comes back to a listener on localhost with the token.
+pub async fn login(settings: &Settings, provider: Provider, open_browser: bool) -> anyhow::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    // Only the browser that went through this login knows the path
+    let path = format!("/callback/{:032x}", rand::random::<u128>());
+    let redirect_uri = format!("http://127.0.0.1:{}{}", listener.local_addr()?.port(), path);
+    let login_url = reqwest::Url::parse_with_params(
+        &format!("{}/api/gateway/login", settings.url.trim_end_matches('/')),
+        &[("redirect_uri", redirect_uri.as_str()), ("provider", provider.as_str())],
+    )?;
+
+    if open_browser && webbrowser::open(login_url.as_str()).is_ok() {
+        println!("Continue the login in your browser. If it didn't open, go to:");
+    } else {
+        println!("Open this URL in a browser to log in:");
+    }
+    println!("  {}", login_url);
+
+    let params = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_callback(&listener, &path))
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out waiting for the login to finish in the browser"))??;
+    if let Some(error) = params.get("error") {
+        anyhow::bail!("Login failed: {}", error);
+    }
+    let Some(token) = params.get("token").filter(|t| !t.is_empty()) else {
+        anyhow::bail!("Login failed: the server sent no token");
+    };
+
+    let credential = Credential {
+        token: token.clone(),
+        kind: CredentialKind::Gateway,
+        email: params.get("email").cloned(),
+        name: params.get("name").cloned(),
+        provider: params.get("provider").cloned().or_else(|| Some(provider.as_str().to_string())),
+        saved_at: chrono::Utc::now(),
+    };
+    let who = credential.email.clone().unwrap_or_else(|| "unknown user".to_string());
+    save(&settings.url, credential)?;
+    println!("✓ Logged in to {} as {}", settings.url, who);
+    warn_overridden(settings);
+    Ok(())
+}
+
+/// Accepts connections until the browser comes back on `path`; returns the
+/// query parameters of that request.
+async fn wait_for_callback(listener: &TcpListener, path: &str) -> anyhow::Result<HashMap<String, String>> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let Some(target) = read_request_target(&mut stream).await else {
+            continue;
+        };
+        let Ok(url) = reqwest::Url::parse(&format!("http://127.0.0.1{}", target)) else {
+            respond(&mut stream, "400 Bad Request", "Bad request").await;
+            continue;
+        };
+        if url.path() != path {
+            // e.g. the browser asking for /favicon.ico
+            respond(&mut stream, "404 Not Found", "Not found").await;
+            continue;
+        }
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        let page = if params.contains_key("token") {
+            "Logged in to Agelum. You can close this tab and go back to the terminal."
+        } else {
+            "The Agelum login failed. See the terminal for details."
+        };
+        respond(&mut stream, "200 OK", page).await;
+        return Ok(params);
+    }
+}
+
+/// Target of an HTTP request line (`GET <target> HTTP/1.1`).
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 64 * 1024 {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next()?.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Some(target.to_string()),
+        _ => None,
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = format!(
+        "<!doctype html><html><body style=\"font-family: sans-serif; margin: 4em\"><p>{}</p></body></html>",
+        message
+    );
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Saves an API key for the server; reads it from stdin when not given, so it
+/// doesn't end up in the shell history.
+pub fn set_token(settings: &Settings, token: Option<String>) -> anyhow::Result<()> {
+    let token = match token {
+        Some(token) => token,
+        None if interactive::is_interactive() => {
+            dialoguer::Password::new().with_prompt("API key").interact()?
+        }
+        None => {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line
+        }
+    };
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        anyhow::bail!("The token is empty");
+    }
+    if reqwest::header::HeaderValue::from_str(&token).is_err() {
+        anyhow::bail!("The token contains characters not allowed in a header");
+    }
+
+    save_api_key(&settings.url, &token)?;
+    println!("✓ Saved API key {} for {}", config::mask(&token), settings.url);
+    warn_overridden(settings);
+    Ok(())
+}
+
+/// Shows which credentials requests to the server carry, and whether the server
+/// takes them.
+pub async fn status(client: &reqwest::Client, settings: &Settings) -> anyhow::Result<()> {
+    println!("Server:  {}", settings.url);
+    let Some(token) = &settings.token else {
+        println!("Not logged in; run `agelum auth login` or `agelum auth token set`");
+        return Ok(());
+    };
+
+    let source = match settings.token_source {
+        Some(TokenSource::Environment) => "AGELUM_TOKEN".to_string(),
+        Some(TokenSource::Profile) => config::config_path()?.display().to_string(),
+        Some(TokenSource::Credentials) | None => credentials::credentials_path()?.display().to_string(),
+    };
+    println!("Token:   {} (from {})", config::mask(token), source);
+
+    if settings.token_source == Some(TokenSource::Credentials)
+        && let Some(credential) = CredentialStore::load(&credentials::credentials_path()?)?.get(&settings.url)
+    {
+        match credential.kind {
+            CredentialKind::Gateway => {
+                let who = match (&credential.name, &credential.email) {
+                    (Some(name), Some(email)) if name != email => format!("{} <{}>", name, email),
+                    (_, Some(email)) => email.clone(),
+                    (Some(name), None) => name.clone(),
+                    (None, None) => "unknown user".to_string(),
+                };
+                let via = credential.provider.as_deref().map(|p| format!(" via {}", p)).unwrap_or_default();
+                println!("User:    {}{}", who, via);
+            }
+            CredentialKind::ApiKey => println!("Kind:    API key"),
+        }
+        println!(
+            "Saved:   {}",
+            credential.saved_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    if let Some(expires) = credentials::expires_at(token) {
+        let local = expires.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+        if expires <= chrono::Utc::now() {
+            println!("⚠ Expired on {}; run `agelum auth login` again", local);
+        } else {
+            println!("Expires: {}", local);
+        }
+    }
+
+    match client.get(format!("{}/api/repositories", settings.url)).dispatch().await {
+        Ok(resp) if resp.status() == reqwest::StatusCode::UNAUTHORIZED || resp.status() == reqwest::StatusCode::FORBIDDEN => {
+            println!("⚠ The server rejects the credentials ({})", resp.status());
+        }
+        Ok(resp) if resp.status().is_success() => println!("✓ The server accepts the requests"),
+        Ok(resp) => println!("⚠ The server answered {}", resp.status()),
+        Err(e) => println!("⚠ {}", e),
+    }
+    Ok(())
+}
+
+/// Forgets the credentials saved for the server.
+pub fn logout(settings: &Settings) -> anyhow::Result<()> {
+    let path = credentials::credentials_path()?;
+    let mut store = CredentialStore::load(&path)?;
+    if store.remove(&settings.url).is_some() {
+        store.save(&path)?;
+        println!("✓ Logged out of {}", settings.url);
+    } else {
+        println!("No saved credentials for {}", settings.url);
+    }
+    match settings.token_source {
+        Some(TokenSource::Environment) => println!("⚠ AGELUM_TOKEN is still set and sent with every request"),
+        Some(TokenSource::Profile) => println!("⚠ The profile's token is still sent with every request"),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Saves an API key as the credentials for the server.
+pub fn save_api_key(url: &str, token: &str) -> anyhow::Result<()> {
+    save(
+        url,
+        Credential {
+            token: token.to_string(),
+            kind: CredentialKind::ApiKey,
+            email: None,
+            name: None,
+            provider: None,
+            saved_at: chrono::Utc::now(),
+        },
+    )
+}
+
+fn save(url: &str, credential: Credential) -> anyhow::Result<()> {
+    let path = credentials::credentials_path()?;
+    let mut store = CredentialStore::load(&path)?;
+    store.set(url, credential);
+    store.save(&path)
+}
+
+/// Saved credentials are only used when neither the environment nor the profile
+/// has a token.
+fn warn_overridden(settings: &Settings) {
+    match settings.token_source {
+        Some(TokenSource::Environment) => println!("⚠ AGELUM_TOKEN is set and is sent instead"),
+        Some(TokenSource::Profile) => println!("⚠ The profile has a token, which is sent instead"),
+        _ => {}
+    }
+}
@@ -0,0 +1,330 @@
+use crate::api;
+use crate::tui::{self, Event, Tui};
+use crate::types::{TASK_STATES, Task};
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Columns of the board, one per task state directory.
+const COLUMNS: [&str; 5] = TASK_STATES;
+/// How often the server is asked for changes made elsewhere.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// File changes come in bursts; reload once they settle.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+const HELP: &str = "←/→ column  ↑/↓ task  H/L move task  p preview  PgUp/PgDn scroll  r refresh  q quit";
+
+struct Board {
+    columns: Vec<Vec<Task>>,
+    column: usize,
+    selected: [usize; COLUMNS.len()],
+    preview: bool,
+    scroll: u16,
+    /// Task markdown by path, cleared on refresh
+    contents: HashMap<String, String>,
+    message: Option<(String, bool)>,
+}
+
+impl Board {
+    fn new() -> Self {
+        Board {
+            columns: vec![Vec::new(); COLUMNS.len()],
+            column: 0,
+            selected: [0; COLUMNS.len()],
+            preview: true,
+            scroll: 0,
+            contents: HashMap::new(),
+            message: None,
+        }
+    }
+
+    /// Replaces the tasks, keeping the selection on the same task where possible.
+    fn set_tasks(&mut self, tasks: Vec<Task>) {
+        let current = self.current().map(|t| t.id.clone());
+        let mut columns = vec![Vec::new(); COLUMNS.len()];
+        for task in tasks {
+            if let Some(i) = COLUMNS.iter().position(|c| *c == task.state) {
+                columns[i].push(task);
+            }
+        }
+        self.columns = columns;
+        self.contents.clear();
+        if let Some(id) = current {
+            self.select(&id);
+        }
+        self.clamp();
+    }
+
+    /// Moves the cursor to a task, in whatever column it is now.
+    fn select(&mut self, id: &str) {
+        for (c, tasks) in self.columns.iter().enumerate() {
+            if let Some(i) = tasks.iter().position(|t| t.id == id) {
+                self.column = c;
+                self.selected[c] = i;
+            }
+        }
+    }
+
+    fn clamp(&mut self) {
+        for (c, tasks) in self.columns.iter().enumerate() {
+            self.selected[c] = self.selected[c].min(tasks.len().saturating_sub(1));
+        }
+    }
+
+    fn current(&self) -> Option<&Task> {
+        self.columns.get(self.column)?.get(self.selected[self.column])
+    }
+
+    /// Changes the selected task, resetting the preview scroll.
+    fn set_cursor(&mut self, column: usize, index: usize) {
+        self.column = column;
+        self.selected[column] = index.min(self.columns[column].len().saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    /// What identifies the board's content, to tell whether a poll changed it.
+    fn fingerprint(tasks: &[Task]) -> Vec<(String, String, String)> {
+        let mut fingerprint: Vec<_> = tasks
+            .iter()
+            .map(|t| (t.id.clone(), t.state.clone(), t.title.clone()))
+            .collect();
+        fingerprint.sort();
+        fingerprint
+    }
+}
+
+/// Full-screen Kanban board of the repository's tasks.
+pub async fn execute(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<()> {
+    // Fail before taking over the terminal when the server isn't there
+    let tasks = api::fetch_tasks(client, url, repo).await?;
+    let tasks_dir = api::fetch_repository(client, url, repo)
+        .await
+        .ok()
+        .map(|r| Path::new(&r.path).join(".agelum").join("work").join("tasks"));
+
+    let mut board = Board::new();
+    let mut last = Board::fingerprint(&tasks);
+    board.set_tasks(tasks);
+
+    let mut tui = Tui::start()?;
+    // Only works when the server runs on this machine; polling covers the rest
+    let _watcher = tasks_dir.filter(|dir| dir.is_dir()).and_then(|dir| tui.watch(&dir).ok());
+
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    poll.tick().await;
+    let mut dirty = false;
+    let mut reload = false;
+    loop {
+        load_preview(client, url, &mut board).await;
+        tui.terminal.draw(|frame| draw(frame, repo, &board))?;
+
+        let event = tokio::select! {
+            event = tui.events.recv() => event,
+            _ = poll.tick() => {
+                reload = true;
+                None
+            }
+            _ = tokio::time::sleep(DEBOUNCE), if dirty => {
+                dirty = false;
+                reload = true;
+                None
+            }
+        };
+        match event {
+            Some(Event::Key(key)) => {
+                if !handle_key(client, url, repo, &mut board, key.code, key.modifiers).await {
+                    break;
+                }
+                if key.code == KeyCode::Char('r') {
+                    reload = true;
+                }
+            }
+            Some(Event::Changed) => dirty = true,
+            Some(Event::Resize) | None => {}
+        }
+
+        if reload {
+            reload = false;
+            match api::fetch_tasks(client, url, repo).await {
+                Ok(tasks) => {
+                    let fingerprint = Board::fingerprint(&tasks);
+                    if fingerprint != last {
+                        last = fingerprint;
+                        board.set_tasks(tasks);
+                    }
+                }
+                Err(e) => board.message = Some((format!("Refresh failed: {}", e), true)),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handles one key press; false quits the board.
+async fn handle_key(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    board: &mut Board,
+    code: KeyCode,
+    modifiers: KeyModifiers,
+) -> bool {
+    let shift = modifiers.contains(KeyModifiers::SHIFT);
+    let column = board.column;
+    let index = board.selected[column];
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return false,
+        KeyCode::Left if shift => move_current(client, url, repo, board, -1).await,
+        KeyCode::Right if shift => move_current(client, url, repo, board, 1).await,
+        KeyCode::Char('H') => move_current(client, url, repo, board, -1).await,
+        KeyCode::Char('L') => move_current(client, url, repo, board, 1).await,
+        KeyCode::Left | KeyCode::Char('h') => {
+            let previous = column.saturating_sub(1);
+            board.set_cursor(previous, board.selected[previous]);
+        }
+        KeyCode::Right | KeyCode::Char('l') => {
+            let next = (column + 1).min(COLUMNS.len() - 1);
+            board.set_cursor(next, board.selected[next]);
+        }
+        KeyCode::Up | KeyCode::Char('k') => board.set_cursor(column, index.saturating_sub(1)),
+        KeyCode::Down | KeyCode::Char('j') => board.set_cursor(column, index + 1),
+        KeyCode::Char('g') | KeyCode::Home => board.set_cursor(column, 0),
+        KeyCode::Char('G') | KeyCode::End => board.set_cursor(column, usize::MAX),
+        KeyCode::Char('p') => board.preview = !board.preview,
+        KeyCode::PageDown => board.scroll = board.scroll.saturating_add(10),
+        KeyCode::PageUp => board.scroll = board.scroll.saturating_sub(10),
+        KeyCode::Char('r') => {
+            board.contents.clear();
+            board.message = None;
+        }
+        _ => {}
+    }
+    true
+}
+
+/// Moves the selected task to the neighbouring column, through the same action
+/// as `agelum move`.
+async fn move_current(client: &reqwest::Client, url: &str, repo: &str, board: &mut Board, direction: isize) {
+    let Some(task) = board.current().cloned() else {
+        return;
+    };
+    let Some(target) = board.column.checked_add_signed(direction).filter(|c| *c < COLUMNS.len()) else {
+        return;
+    };
+    let to_state = COLUMNS[target];
+    match api::move_task(client, url, repo, &task.id, &task.state, to_state).await {
+        Ok(()) => {
+            board.message = Some((format!("✓ Moved {} to {}", task.id, to_state), false));
+            match api::fetch_tasks(client, url, repo).await {
+                Ok(tasks) => {
+                    board.set_tasks(tasks);
+                    board.select(&task.id);
+                }
+                Err(e) => board.message = Some((format!("Refresh failed: {}", e), true)),
+            }
+        }
+        Err(e) => board.message = Some((format!("Error moving task: {}", e), true)),
+    }
+}
+
+/// Fetches the markdown of the selected task for the preview, once per refresh.
+async fn load_preview(client: &reqwest::Client, url: &str, board: &mut Board) {
+    if !board.preview {
+        return;
+    }
+    let Some(path) = board.current().and_then(|t| t.path.clone()) else {
+        return;
+    };
+    if board.contents.contains_key(&path) {
+        return;
+    }
+    let content = api::read_file(client, url, &path)
+        .await
+        .unwrap_or_else(|e| format!("Could not read {}: {}", path, e));
+    board.contents.insert(path, content);
+}
+
+fn draw(frame: &mut Frame, repo: &str, board: &Board) {
+    let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let (columns_area, preview_area) = if board.preview {
+        let [columns, preview] =
+            Layout::vertical([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main);
+        (columns, Some(preview))
+    } else {
+        (main, None)
+    };
+
+    let areas = Layout::horizontal([Constraint::Ratio(1, COLUMNS.len() as u32); COLUMNS.len()]).split(columns_area);
+    for (c, area) in areas.iter().enumerate() {
+        draw_column(frame, *area, board, c);
+    }
+    if let Some(area) = preview_area {
+        draw_preview(frame, area, board);
+    }
+
+    let status_line = match &board.message {
+        Some((message, true)) => Line::styled(message.clone(), Style::default().fg(Color::Red)),
+        Some((message, false)) => Line::styled(message.clone(), Style::default().fg(Color::Green)),
+        None => Line::from(vec![
+            Span::styled(format!(" {} ", repo), Style::default().add_modifier(Modifier::REVERSED)),
+            Span::styled(format!(" {}", HELP), Style::default().fg(Color::DarkGray)),
+        ]),
+    };
+    frame.render_widget(Paragraph::new(status_line), status);
+}
+
+fn draw_column(frame: &mut Frame, area: Rect, board: &Board, c: usize) {
+    let tasks = &board.columns[c];
+    let focused = c == board.column;
+    let border = if focused { Style::default().fg(Color::Cyan) } else { Style::default() };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(format!(" {} ({}) ", COLUMNS[c], tasks.len()));
+
+    let items: Vec<ListItem> = tasks
+        .iter()
+        .map(|t| {
+            let mut lines = vec![Line::from(t.title.clone())];
+            let details: Vec<&str> = [t.epic.as_deref(), t.priority.as_deref()].into_iter().flatten().collect();
+            if !details.is_empty() {
+                lines.push(Line::styled(format!("  {}", details.join(" · ")), Style::default().fg(Color::DarkGray)));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(if focused {
+            Style::default().bg(Color::Cyan).fg(Color::Black)
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        });
+    let mut state = ListState::default().with_selected((!tasks.is_empty()).then_some(board.selected[c]));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_preview(frame: &mut Frame, area: Rect, board: &Board) {
+    let task = board.current();
+    let title = task.map(|t| format!(" {} ", t.id)).unwrap_or_else(|| " Preview ".to_string());
+    let text = match task {
+        None => tui::markdown("No task selected"),
+        Some(t) => match t.path.as_ref().and_then(|p| board.contents.get(p)) {
+            Some(content) => tui::markdown(content),
+            None if t.path.is_none() => tui::markdown(&format!("# {}\n\nThe server returned no file for this task", t.title)),
+            None => tui::markdown("Loading…"),
+        },
+    };
+    let preview = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false })
+        .scroll((board.scroll, 0));
+    frame.render_widget(preview, area);
+}
@@ -1,4 +1,9 @@
-use crate::types::TestStepResponse;
+use crate::agent::{self, AgentConfig};
+use crate::agent_browser::{Browser, BrowserOptions};
+use crate::api;
+use crate::http::Dispatch;
+use crate::runner;
+use crate::types::TestStep;
 use std::process::Command;
 
 pub async fn execute(
@@ -48,7 +53,7 @@ async fn navigate_test(
     let encoded_repo = urlencoding::encode(repo);
     let request_url = format!("{}/api/tests/{}/steps?repo={}", url, test_id, encoded_repo);
     
-    let resp = client.get(request_url).send().await?;
+    let resp = client.get(request_url).dispatch().await?;
 
     if !resp.status().is_success() {
         eprintln!("Error fetching test steps: {}", resp.status());
@@ -58,29 +63,52 @@ async fn navigate_test(
         return Ok(());
     }
 
-    let steps_resp: TestStepResponse = resp.json().await?;
+    let steps: Vec<TestStep> = resp.json().await?;
     
-    if steps_resp.steps.is_empty() {
+    if steps.is_empty() {
         println!("No steps found for test {}", test_id);
         return Ok(());
     }
 
-    println!("Executing {} steps...", steps_resp.steps.len());
+    // Prompt steps run with the agent from AGELUM_AGENT or AGELUM_AGENT_COMMAND
+    let agent = agent::resolve(client, url, AgentConfig::from_env()).await?;
+    let browser = Browser::new(browser_options(client, url, repo, test_id).await?);
+    browser.prepare().await?;
+
+    println!("Executing {} steps...", steps.len());
     
-    // Execute each step in order
-    for step in steps_resp.steps {
-        println!("\n▶ Step {}: {} {}", step.order, step.command, step.args.join(" "));
-        
-        let mut full_args = vec![step.command];
-        full_args.extend(step.args);
-        
-        passthrough_to_agent_browser(full_args)?;
+    // Execute each step in order, stopping at the first failure
+    for (i, step) in steps.iter().enumerate() {
+        println!("\n▶ Step {}: {}", i + 1, step);
+
+        match runner::run_step(step, &browser, agent.as_ref()).await {
+            Ok(output) => {
+                for line in output.stdout.lines().filter(|l| !l.trim().is_empty()) {
+                    println!("  {}", line);
+                }
+            }
+            Err(e) => {
+                eprintln!("  {}", e);
+                return Ok(());
+            }
+        }
     }
 
     println!("\n✓ Test navigation completed");
     Ok(())
 }
 
+/// Browser settings from the test definition.
+async fn browser_options(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<BrowserOptions> {
+    let test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    Ok(test.browser.unwrap_or_default())
+}
+
 fn passthrough_to_agent_browser(args: Vec<String>) -> anyhow::Result<()> {
     // Execute agent-browser with the provided arguments
     let result = Command::new("agent-browser")
@@ -0,0 +1,33 @@
+use crate::completion;
+use clap_complete::env::EnvCompleter;
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Prints the script that registers completion for the shell. The script calls
+/// this binary back to complete, so values from the server stay current.
+pub fn execute(shell: Shell) -> anyhow::Result<()> {
+    let completer = std::env::current_exe()?;
+    // Complete the name the CLI was invoked as, e.g. `agelum`
+    let bin = std::env::args_os()
+        .next()
+        .and_then(|arg0| std::path::Path::new(&arg0).file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "agelum".to_string());
+    let registration: &dyn EnvCompleter = match shell {
+        Shell::Bash => &clap_complete::env::Bash,
+        Shell::Zsh => &clap_complete::env::Zsh,
+        Shell::Fish => &clap_complete::env::Fish,
+    };
+    registration.write_registration(
+        completion::ENV_VAR,
+        "agelum",
+        &bin,
+        &completer.to_string_lossy(),
+        &mut std::io::stdout(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use crate::config::{self, CliConfig, OutputFormat};
+
+/// Lists the profiles of `~/.agelum/cli.toml`, marking the selected one, and
+/// fails when any of them has problems.
+pub fn profiles(flag: Option<&str>) -> anyhow::Result<()> {
+    let path = config::config_path()?;
+    if !path.exists() {
+        println!("No profiles; create {} with e.g.", path.display());
+        println!();
+        println!("  default_profile = \"local\"");
+        println!();
+        println!("  [profiles.local]");
+        println!("  url = \"{}\"", config::DEFAULT_URL);
+        println!("  repo = \"<repo-name>\"");
+        return Ok(());
+    }
+    let config = CliConfig::load(&path)?;
+    let selected = config.selected(flag);
+    println!("Profiles in {}", path.display());
+
+    let mut found = 0;
+    if let Some(default) = &config.default_profile
+        && !config.profiles.contains_key(default)
+    {
+        println!("⚠ default_profile {} is not defined", default);
+        found += 1;
+    }
+    if let Some(name) = &selected
+        && !config.profiles.contains_key(name)
+    {
+        println!("⚠ Selected profile {} is not defined", name);
+        found += 1;
+    }
+
+    for (name, profile) in &config.profiles {
+        let marker = if selected.as_deref() == Some(name.as_str()) { "*" } else { " " };
+        println!();
+        println!("{} {}", marker, name);
+        println!("    url:             {}", profile.url.as_deref().unwrap_or(config::DEFAULT_URL));
+        println!("    repo:            {}", profile.repo.as_deref().unwrap_or("-"));
+        let output = match profile.output.unwrap_or_default() {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+        };
+        println!("    output:          {}", output);
+        println!("    token:           {}", profile.token.as_deref().map(config::mask).unwrap_or_else(|| "-".to_string()));
+        println!(
+            "    local_path:      {}",
+            profile.local_path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "-".to_string())
+        );
+        println!(
+            "    connect_timeout: {}",
+            profile.connect_timeout.clone().unwrap_or_else(|| humantime::format_duration(config::DEFAULT_CONNECT_TIMEOUT).to_string())
+        );
+        println!(
+            "    timeout:         {}",
+            profile.timeout.clone().unwrap_or_else(|| humantime::format_duration(config::DEFAULT_TIMEOUT).to_string())
+        );
+        println!("    retries:         {}", profile.retries.unwrap_or(config::DEFAULT_RETRIES));
+        let problems = config.validate(name);
+        if problems.is_empty() {
+            println!("    ✓ Valid");
+        } else {
+            found += problems.len();
+            for problem in problems {
+                println!("    ⚠ {}", problem);
+            }
+        }
+    }
+
+    let overrides: Vec<&str> = config::ENV_OVERRIDES
+        .into_iter()
+        .filter(|key| std::env::var_os(key).is_some_and(|v| !v.is_empty()))
+        .collect();
+    if !overrides.is_empty() {
+        println!();
+        println!("Overridden by the environment: {}", overrides.join(", "));
+    }
+
+    if found > 0 {
+        anyhow::bail!("Fix the {} problem(s) above in {}", found, path.display());
+    }
+    Ok(())
+}
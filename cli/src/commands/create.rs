@@ -1,3 +1,4 @@
+use crate::http::Dispatch;
 use crate::types::EntityType;
 
 pub async fn execute(
@@ -41,7 +42,7 @@ async fn create_task(
     let resp = client
         .post(format!("{}/api/tasks", url))
         .json(&body)
-        .send()
+        .dispatch()
         .await?;
 
     if resp.status().is_success() {
@@ -71,9 +72,9 @@ async fn create_test_group(
     });
 
     let resp = client
-        .post(format!("{}/api/test-groups?repo={}", url, encoded_repo))
+        .post(format!("{}/api/tests/groups?repo={}", url, encoded_repo))
         .json(&body)
-        .send()
+        .dispatch()
         .await?;
 
     if resp.status().is_success() {
@@ -107,7 +108,7 @@ async fn create_test(
     let resp = client
         .post(format!("{}/api/tests?repo={}", url, encoded_repo))
         .json(&body)
-        .send()
+        .dispatch()
         .await?;
 
     if resp.status().is_success() {
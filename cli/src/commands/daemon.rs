@@ -0,0 +1,344 @@
+use crate::agent::AgentConfig;
+use crate::api;
+use crate::commands::test_run;
+use crate::config::Settings;
+use crate::schedule::{self, Schedule, ScheduleStore, TargetKind};
+use chrono::Utc;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Longest sleep between checks, so schedules added while the daemon runs are
+/// picked up.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sent to the failure command on stdin and to the webhook as the request body.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Failure {
+    schedule: String,
+    repo: String,
+    test_id: String,
+    test_name: String,
+    execution_id: Option<String>,
+    status: String,
+    error: String,
+    time: String,
+}
+
+/// Runs scheduled tests with the local runner until interrupted. Results go
+/// through the finish endpoint like any local run.
+pub async fn execute(
+    client: &reqwest::Client,
+    settings: &Settings,
+    on_failure: Option<String>,
+    webhook: Option<String>,
+    once: bool,
+) -> anyhow::Result<()> {
+    let path = schedule::schedule_path()?;
+    let store = ScheduleStore::load(&path)?;
+    log(&format!(
+        "Daemon started with {} schedules from {}",
+        store.schedules.len(),
+        path.display()
+    ));
+    let defaults = (on_failure, webhook);
+
+    loop {
+        let tick = async {
+            let store = ScheduleStore::load(&path)?;
+            let now = Utc::now();
+            for due in store.schedules.iter().filter(|s| s.interval().is_ok() && s.is_due(now)) {
+                run_schedule(client, settings, due, &defaults).await?;
+            }
+            anyhow::Ok(())
+        };
+        tokio::select! {
+            result = tick => result?,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        if once {
+            return Ok(());
+        }
+
+        let store = ScheduleStore::load(&path)?;
+        let wait = store
+            .schedules
+            .iter()
+            .filter(|s| s.interval().is_ok())
+            .map(|s| match s.next_run() {
+                Some(next) => (next - Utc::now()).to_std().unwrap_or_default(),
+                None => Duration::ZERO,
+            })
+            .min()
+            .unwrap_or(POLL_INTERVAL)
+            .min(POLL_INTERVAL);
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    log("Daemon stopped");
+    Ok(())
+}
+
+/// Runs the tests of one schedule and records the outcome in the state file.
+async fn run_schedule(
+    client: &reqwest::Client,
+    settings: &Settings,
+    schedule: &Schedule,
+    defaults: &(Option<String>, Option<String>),
+) -> anyhow::Result<()> {
+    let started = Utc::now();
+    log(&format!("▶ {} {} ({})", schedule.id, schedule.target, schedule.repo));
+
+    // Schedules may be on other servers than the daemon's, with credentials of
+    // their own
+    let tests = match settings.for_server(&schedule.url).and_then(|s| s.authorize()) {
+        Ok(()) => tests_of(client, schedule).await,
+        Err(e) => Err(e),
+    };
+    let (status, error) = match tests {
+        Ok(tests) => {
+            let mut status = "passed";
+            let mut first_error = None;
+            for (test_id, test_name) in tests {
+                let (test_status, execution_id, error) = run_test(client, schedule, &test_id).await;
+                log(&format!("  {} {}: {}", schedule.id, test_id, test_status));
+                if test_status == "passed" {
+                    continue;
+                }
+                status = if test_status == "error" && status == "passed" { "error" } else { "failed" };
+                let error = error.unwrap_or_else(|| format!("Test {}", test_status));
+                first_error.get_or_insert_with(|| error.clone());
+                let failure = Failure {
+                    schedule: schedule.id.clone(),
+                    repo: schedule.repo.clone(),
+                    test_id,
+                    test_name,
+                    execution_id,
+                    status: test_status,
+                    error,
+                    time: Utc::now().to_rfc3339(),
+                };
+                notify(schedule, defaults, &failure).await;
+            }
+            (status.to_string(), first_error)
+        }
+        Err(e) => {
+            log(&format!("  {} could not list tests: {}", schedule.id, e));
+            ("error".to_string(), Some(e.to_string()))
+        }
+    };
+
+    // Reload, so schedules added or removed during the run are kept
+    let path = schedule::schedule_path()?;
+    let mut store = ScheduleStore::load(&path)?;
+    if let Some(saved) = store.schedules.iter_mut().find(|s| s.id == schedule.id) {
+        saved.last_run = Some(started);
+        saved.last_status = Some(status);
+        saved.last_error = error;
+        store.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Ids and names of the tests a schedule runs.
+async fn tests_of(client: &reqwest::Client, schedule: &Schedule) -> anyhow::Result<Vec<(String, String)>> {
+    let tests = api::fetch_tests(client, &schedule.url, &schedule.repo).await?;
+    let matching: Vec<_> = tests
+        .into_iter()
+        .filter(|t| match schedule.kind {
+            TargetKind::Test => t.id == schedule.target,
+            TargetKind::Group => t.group.as_deref() == Some(schedule.target.as_str()),
+        })
+        .map(|t| (t.id, t.name))
+        .collect();
+    if matching.is_empty() {
+        anyhow::bail!("No tests found for {}", schedule.target);
+    }
+    Ok(matching)
+}
+
+/// Status, execution id and failure message of one run. Errors before the
+/// run could finish, such as an unreachable server, are reported as "error".
+async fn run_test(
+    client: &reqwest::Client,
+    schedule: &Schedule,
+    test_id: &str,
+) -> (String, Option<String>, Option<String>) {
+    // Prompt steps use the agent from the environment or the run configs
+    match test_run::run_local(client, &schedule.url, &schedule.repo, test_id, AgentConfig::default()).await {
+        Ok(outcome) => {
+            let failure = outcome.cases.into_iter().find_map(|c| c.failure);
+            (
+                outcome.status.unwrap_or_else(|| "unknown".to_string()),
+                outcome.execution_id,
+                failure,
+            )
+        }
+        Err(e) => ("error".to_string(), None, Some(e.to_string())),
+    }
+}
+
+/// Runs the failure command and calls the webhook of the schedule, falling back
+/// to the daemon's. Notification errors are logged, not fatal.
+async fn notify(
+    schedule: &Schedule,
+    defaults: &(Option<String>, Option<String>),
+    failure: &Failure,
+) {
+    if let Some(command) = schedule.on_failure.as_ref().or(defaults.0.as_ref())
+        && let Err(e) = run_command(command, failure).await
+    {
+        log(&format!("  ⚠ Failure command failed: {}", e));
+    }
+    if let Some(webhook) = schedule.webhook.as_ref().or(defaults.1.as_ref()) {
+        // A client of its own, as the webhook is not the server and must not get
+        // its token
+        let result = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+            Ok(webhooks) => webhooks.post(webhook).json(failure).send().await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(resp) if !resp.status().is_success() => {
+                log(&format!("  ⚠ Webhook {} answered {}", webhook, resp.status()))
+            }
+            Ok(_) => {}
+            Err(e) => log(&format!("  ⚠ Webhook {} failed: {}", webhook, e)),
+        }
+    }
+}
+
+/// Runs the failure command with `sh -c`, with the failure as JSON on stdin and
+/// its fields in `AGELUM_*` variables.
+async fn run_command(command: &str, failure: &Failure) -> anyhow::Result<()> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .env("AGELUM_SCHEDULE", &failure.schedule)
+        .env("AGELUM_REPO", &failure.repo)
+        .env("AGELUM_TEST_ID", &failure.test_id)
+        .env("AGELUM_TEST_NAME", &failure.test_name)
+        .env("AGELUM_EXECUTION_ID", failure.execution_id.as_deref().unwrap_or_default())
+        .env("AGELUM_STATUS", &failure.status)
+        .env("AGELUM_ERROR", &failure.error)
+        .stdin(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that doesn't read its input closes the pipe early
+        let _ = stdin.write_all(serde_json::to_string(failure)?.as_bytes()).await;
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", command, status);
+    }
+    Ok(())
+}
+
+fn log(message: &str) {
+    println!("[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Accepts one request, answers 200 and returns the request as received.
+    async fn stand_in(listener: TcpListener) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length || n == 0 {
+                    break;
+                }
+            }
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    }
+
+    #[tokio::test]
+    async fn webhook_gets_the_failure_and_no_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        // Even a token for the webhook's own host stays out of the notification
+        http::authorize(&base, "secret-token").unwrap();
+        let server = tokio::spawn(stand_in(listener));
+
+        let schedule = Schedule {
+            id: "nightly".to_string(),
+            url: "http://127.0.0.1:6500".to_string(),
+            repo: "demo".to_string(),
+            target: "login".to_string(),
+            kind: TargetKind::Test,
+            every: "15m".to_string(),
+            on_failure: None,
+            webhook: Some(format!("{}/hook", base)),
+            last_run: None,
+            last_status: None,
+            last_error: None,
+        };
+        let failure = Failure {
+            schedule: "nightly".to_string(),
+            repo: "demo".to_string(),
+            test_id: "login".to_string(),
+            test_name: "Login".to_string(),
+            execution_id: Some("exec-1".to_string()),
+            status: "failed".to_string(),
+            error: "Step 2 failed".to_string(),
+            time: "2026-01-01T00:00:00Z".to_string(),
+        };
+        notify(&schedule, &(None, None), &failure).await;
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"), "{}", request);
+        assert!(!request.to_lowercase().contains("authorization"), "{}", request);
+        let body: serde_json::Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["schedule"], "nightly");
+        assert_eq!(body["testId"], "login");
+        assert_eq!(body["executionId"], "exec-1");
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["error"], "Step 2 failed");
+    }
+
+    #[tokio::test]
+    async fn failure_command_gets_the_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("failure");
+        let command = format!("cat > {0}.json; echo \"$AGELUM_TEST_ID $AGELUM_STATUS\" > {0}.env", out.display());
+        let failure = Failure {
+            schedule: "nightly".to_string(),
+            repo: "demo".to_string(),
+            test_id: "login".to_string(),
+            test_name: "Login".to_string(),
+            execution_id: None,
+            status: "error".to_string(),
+            error: "Server unreachable".to_string(),
+            time: "2026-01-01T00:00:00Z".to_string(),
+        };
+        run_command(&command, &failure).await.unwrap();
+
+        let body: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.with_extension("json")).unwrap()).unwrap();
+        assert_eq!(body["error"], "Server unreachable");
+        assert_eq!(std::fs::read_to_string(out.with_extension("env")).unwrap().trim(), "login error");
+    }
+}
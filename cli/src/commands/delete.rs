@@ -1,14 +1,27 @@
+use crate::api;
+use crate::http::Dispatch;
+use crate::interactive::confirm;
+use crate::picker;
 use crate::types::EntityType;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn execute(
     client: &reqwest::Client,
     url: &str,
-    _repo: &str,
+    repo: &str,
     entity: EntityType,
-    path: &str,
+    path: Option<String>,
+    id: Option<String>,
+    cascade: bool,
+    yes: bool,
 ) -> anyhow::Result<()> {
     match entity {
-        EntityType::Doc => delete_doc(client, url, path).await,
+        EntityType::Doc => delete_doc(client, url, &picker::doc(client, url, repo, path, "--path").await?).await,
+        EntityType::Test => delete_test(client, url, repo, &picker::test(client, url, repo, id, "--id").await?).await,
+        EntityType::TestGroup => {
+            let name = picker::test_group(client, url, repo, id, "--id").await?;
+            delete_test_group(client, url, repo, &name, cascade, yes).await
+        }
         _ => {
             println!("Delete command for {} not yet implemented", entity);
             Ok(())
@@ -19,7 +32,7 @@ pub async fn execute(
 async fn delete_doc(client: &reqwest::Client, url: &str, path: &str) -> anyhow::Result<()> {
     let encoded_path = urlencoding::encode(path);
     let request_url = format!("{}/api/file?path={}", url, encoded_path);
-    let resp = client.delete(request_url).send().await?;
+    let resp = client.delete(request_url).dispatch().await?;
 
     if resp.status().is_success() {
         println!("File deleted successfully");
@@ -31,3 +44,65 @@ async fn delete_doc(client: &reqwest::Client, url: &str, path: &str) -> anyhow::
 
     Ok(())
 }
+
+async fn delete_test(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+) -> anyhow::Result<()> {
+    // The server reports success for unknown ids, so check the index first
+    let test = api::fetch_test(client, url, repo, test_id).await?;
+    api::delete_test(client, url, repo, &test.id).await?;
+    println!("✓ Test deleted: {} ({})", test.name, test.id);
+    Ok(())
+}
+
+/// Groups are folders under `.agelum/tests`. A group that still holds tests is
+/// only deleted with `--cascade`, after confirming the list of tests to remove.
+async fn delete_test_group(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    name: &str,
+    cascade: bool,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let tests: Vec<_> = api::fetch_tests(client, url, repo)
+        .await?
+        .into_iter()
+        .filter(|t| t.group.as_deref() == Some(name))
+        .collect();
+
+    if !tests.is_empty() {
+        if !cascade {
+            anyhow::bail!(
+                "Group {} contains {} tests. Pass --cascade to delete them with the group",
+                name,
+                tests.len()
+            );
+        }
+
+        println!("Group {} contains {} tests:", name, tests.len());
+        for test in &tests {
+            println!("  - [{}] {}", test.id, test.name);
+        }
+        if !yes && !confirm("Delete the group and all of its tests?", false)? {
+            println!("Aborted");
+            return Ok(());
+        }
+
+        for test in &tests {
+            api::delete_test(client, url, repo, &test.id).await?;
+            println!("✓ Test deleted: {} ({})", test.name, test.id);
+        }
+    }
+
+    // The folders of the deleted tests may already have taken an empty group with them
+    if !api::delete_test_group(client, url, repo, name, cascade).await? && tests.is_empty() {
+        anyhow::bail!("Test group not found: {}", name);
+    }
+
+    println!("✓ Test group deleted: {}", name);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use crate::api;
+use crate::commands::auth;
+use crate::config::{OutputFormat, Settings};
+use crate::interactive::confirm;
+
+pub async fn list(client: &reqwest::Client, settings: &Settings) -> anyhow::Result<()> {
+    let keys = api::fetch_api_keys(client, &settings.url).await?;
+    if settings.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&keys)?);
+        return Ok(());
+    }
+    if keys.is_empty() {
+        println!("No API keys; create one with `agelum keys create --name <name>`");
+        return Ok(());
+    }
+
+    println!("{:<38} {:<24} {:<20} {:<20}", "ID", "NAME", "CREATED", "LAST USED");
+    for key in &keys {
+        println!(
+            "{:<38} {:<24} {:<20} {:<20}",
+            key.id,
+            key.name,
+            local_time(&key.created_at),
+            key.last_used_at.as_deref().map(local_time).unwrap_or_else(|| "-".to_string())
+        );
+    }
+    Ok(())
+}
+
+/// Creates a key and shows its secret, which the server only returns this once.
+/// With `save`, the CLI also uses it for the server from now on.
+pub async fn create(client: &reqwest::Client, settings: &Settings, name: &str, save: bool) -> anyhow::Result<()> {
+    let created = api::create_api_key(client, &settings.url, name).await?;
+    if save {
+        auth::save_api_key(&settings.url, &created.raw_key)?;
+    }
+    if settings.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&created)?);
+        return Ok(());
+    }
+
+    println!("✓ Created API key {} ({})", created.key.name, created.key.id);
+    if save {
+        println!("  Saved as the CLI's credentials for {}", settings.url);
+    } else {
+        println!("  Key: {}", created.raw_key);
+        println!("  Copy it now; the server only keeps a hash and can't show it again");
+    }
+    Ok(())
+}
+
+pub async fn revoke(client: &reqwest::Client, settings: &Settings, id: &str, yes: bool) -> anyhow::Result<()> {
+    let keys = api::fetch_api_keys(client, &settings.url).await?;
+    // The server answers success for ids it doesn't know
+    let Some(key) = keys.iter().find(|k| k.id == id) else {
+        anyhow::bail!("No API key with id {}; see `agelum keys ls`", id);
+    };
+    if !yes && !confirm(&format!("Revoke API key {} ({})?", key.name, key.id), false)? {
+        println!("Aborted");
+        return Ok(());
+    }
+    api::revoke_api_key(client, &settings.url, id).await?;
+    println!("✓ Revoked API key {} ({})", key.name, key.id);
+    Ok(())
+}
+
+fn local_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
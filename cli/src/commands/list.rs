@@ -1,15 +1,19 @@
-use crate::types::{EntityType, TaskResponse, TestGroupResponse, TestResponse};
+use crate::api;
+use crate::config::OutputFormat;
+use crate::http::Dispatch;
+use crate::types::{EntityType, TaskResponse};
 
 pub async fn execute(
     client: &reqwest::Client,
     url: &str,
     repo: &str,
     entity: EntityType,
+    output: OutputFormat,
 ) -> anyhow::Result<()> {
     match entity {
-        EntityType::Task => list_tasks(client, url, repo).await,
-        EntityType::TestGroup => list_test_groups(client, url, repo).await,
-        EntityType::Test => list_tests(client, url, repo).await,
+        EntityType::Task => list_tasks(client, url, repo, output).await,
+        EntityType::TestGroup => list_test_groups(client, url, repo, output).await,
+        EntityType::Test => list_tests(client, url, repo, output).await,
         _ => {
             println!("List command for {} not yet implemented", entity);
             Ok(())
@@ -17,10 +21,10 @@ pub async fn execute(
     }
 }
 
-async fn list_tasks(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<()> {
+async fn list_tasks(client: &reqwest::Client, url: &str, repo: &str, output: OutputFormat) -> anyhow::Result<()> {
     let encoded_repo = urlencoding::encode(repo);
     let request_url = format!("{}/api/tasks?repo={}", url, encoded_repo);
-    let resp = client.get(request_url).send().await?;
+    let resp = client.get(request_url).dispatch().await?;
 
     if !resp.status().is_success() {
         eprintln!("Error fetching tasks: {}", resp.status());
@@ -31,6 +35,10 @@ async fn list_tasks(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::
     }
 
     let task_resp: TaskResponse = resp.json().await?;
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&task_resp.tasks)?);
+        return Ok(());
+    }
     for task in task_resp.tasks {
         println!("- [{}] {} (State: {})", task.id, task.title, task.state);
     }
@@ -38,45 +46,28 @@ async fn list_tasks(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::
     Ok(())
 }
 
-async fn list_test_groups(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<()> {
-    let encoded_repo = urlencoding::encode(repo);
-    let request_url = format!("{}/api/test-groups?repo={}", url, encoded_repo);
-    let resp = client.get(request_url).send().await?;
-
-    if !resp.status().is_success() {
-        eprintln!("Error fetching test groups: {}", resp.status());
-        if let Ok(text) = resp.text().await {
-            eprintln!("{}", text);
-        }
+async fn list_test_groups(client: &reqwest::Client, url: &str, repo: &str, output: OutputFormat) -> anyhow::Result<()> {
+    // Groups are the folders under `.agelum/tests`, listed by name
+    let groups = api::fetch_test_groups(client, url, repo).await?;
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&groups)?);
         return Ok(());
     }
-
-    let group_resp: TestGroupResponse = resp.json().await?;
-    for group in group_resp.groups {
-        println!("- [{}] {}", group.id, group.name);
-        if let Some(desc) = group.description {
-            println!("  Description: {}", desc);
-        }
+    for group in groups {
+        println!("- {}", group);
     }
 
     Ok(())
 }
 
-async fn list_tests(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<()> {
-    let encoded_repo = urlencoding::encode(repo);
-    let request_url = format!("{}/api/tests?repo={}", url, encoded_repo);
-    let resp = client.get(request_url).send().await?;
-
-    if !resp.status().is_success() {
-        eprintln!("Error fetching tests: {}", resp.status());
-        if let Ok(text) = resp.text().await {
-            eprintln!("{}", text);
-        }
+async fn list_tests(client: &reqwest::Client, url: &str, repo: &str, output: OutputFormat) -> anyhow::Result<()> {
+    // The test index, as a bare array
+    let tests = api::fetch_tests(client, url, repo).await?;
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&tests)?);
         return Ok(());
     }
-
-    let test_resp: TestResponse = resp.json().await?;
-    for test in test_resp.tests {
+    for test in tests {
         let group_info = test.group.as_ref().map(|g| format!(" [Group: {}]", g)).unwrap_or_default();
         println!("- [{}] {}{}", test.id, test.name, group_info);
         if let Some(desc) = test.description {
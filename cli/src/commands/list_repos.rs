@@ -1,9 +1,11 @@
+use crate::config::OutputFormat;
+use crate::http::Dispatch;
 use crate::types::RepositoryResponse;
 
-pub async fn execute(client: &reqwest::Client, url: &str) -> anyhow::Result<()> {
+pub async fn execute(client: &reqwest::Client, url: &str, output: OutputFormat) -> anyhow::Result<()> {
     let resp = client
         .get(format!("{}/api/repositories", url))
-        .send()
+        .dispatch()
         .await?;
     
     if !resp.status().is_success() {
@@ -18,6 +20,10 @@ pub async fn execute(client: &reqwest::Client, url: &str) -> anyhow::Result<()>
     if let Some(err) = resp_json.error {
         eprintln!("API Error: {}", err);
     }
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&resp_json.repositories)?);
+        return Ok(());
+    }
     for repo in resp_json.repositories {
         println!("- {} ({})", repo.name, repo.path);
     }
@@ -13,3 +13,22 @@ pub mod test_finish;
 pub mod test_executions;
 pub mod test_steps;
 pub mod browser;
+pub mod test_diff;
+pub mod test_step;
+pub mod test_clone;
+pub mod test_sync;
+pub mod test_stats;
+pub mod test_record;
+pub mod test_log;
+pub mod test_compare;
+pub mod test_generate;
+pub mod test_schedule;
+pub mod daemon;
+pub mod board;
+pub mod test_ui;
+pub mod completions;
+pub mod config;
+pub mod auth;
+pub mod keys;
+pub mod settings;
+pub mod status;
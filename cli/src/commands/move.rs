@@ -1,3 +1,5 @@
+use crate::api;
+use crate::picker;
 use crate::types::EntityType;
 
 pub async fn execute(
@@ -5,12 +7,15 @@ pub async fn execute(
     url: &str,
     repo: &str,
     entity: EntityType,
-    entity_id: &str,
+    entity_id: Option<String>,
     from_state: &str,
     to_state: &str,
 ) -> anyhow::Result<()> {
     match entity {
-        EntityType::Task => move_task(client, url, repo, entity_id, from_state, to_state).await,
+        EntityType::Task => {
+            let task_id = picker::task(client, url, repo, entity_id, "--id").await?;
+            move_task(client, url, repo, &task_id, from_state, to_state).await
+        }
         _ => {
             println!("Move command for {} not yet implemented", entity);
             Ok(())
@@ -26,27 +31,9 @@ async fn move_task(
     from_state: &str,
     to_state: &str,
 ) -> anyhow::Result<()> {
-    let body = serde_json::json!({
-        "repo": repo,
-        "action": "move",
-        "taskId": task_id,
-        "fromState": from_state,
-        "toState": to_state
-    });
-
-    let resp = client
-        .post(format!("{}/api/tasks", url))
-        .json(&body)
-        .send()
-        .await?;
-
-    if resp.status().is_success() {
-        println!("Task moved successfully");
-    } else {
-        eprintln!("Error moving task: {}", resp.status());
-        let text = resp.text().await?;
-        eprintln!("{}", text);
+    match api::move_task(client, url, repo, task_id, from_state, to_state).await {
+        Ok(()) => println!("Task moved successfully"),
+        Err(e) => eprintln!("Error moving task: {}", e),
     }
-
     Ok(())
 }
@@ -1,14 +1,19 @@
+use crate::http::Dispatch;
+use crate::picker;
 use crate::types::EntityType;
 
 pub async fn execute(
     client: &reqwest::Client,
     url: &str,
-    _repo: &str,
+    repo: &str,
     entity: EntityType,
-    path: &str,
+    path: Option<String>,
 ) -> anyhow::Result<()> {
     match entity {
-        EntityType::Doc => read_doc(client, url, path).await,
+        EntityType::Doc => {
+            let path = picker::doc(client, url, repo, path, "--path").await?;
+            read_doc(client, url, &path).await
+        }
         _ => {
             println!("Read command for {} not yet implemented", entity);
             Ok(())
@@ -19,7 +24,7 @@ pub async fn execute(
 async fn read_doc(client: &reqwest::Client, url: &str, path: &str) -> anyhow::Result<()> {
     let encoded_path = urlencoding::encode(path);
     let request_url = format!("{}/api/file?path={}", url, encoded_path);
-    let resp = client.get(request_url).send().await?;
+    let resp = client.get(request_url).dispatch().await?;
 
     if resp.status().is_success() {
         let json: serde_json::Value = resp.json().await?;
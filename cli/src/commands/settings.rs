@@ -0,0 +1,382 @@
+use crate::api;
+use crate::config::{self, OutputFormat, Settings};
+use crate::interactive::{confirm, open_editor};
+use serde_json::{Map, Value};
+
+// `agelum settings` reads and writes the app's user settings through
+// `/api/settings`. Values are checked against the schema of `UserSettings`
+// (apps/web/src/types/settings.ts) before anything is written, since the
+// server stores whatever it's sent.
+
+enum Schema {
+    String,
+    Bool,
+    Number,
+    OneOf(&'static [&'static str]),
+    List(&'static Schema),
+    Record(&'static [Field]),
+    /// Not checked
+    Any,
+}
+
+struct Field {
+    name: &'static str,
+    schema: Schema,
+    optional: bool,
+}
+
+const fn field(name: &'static str, schema: Schema) -> Field {
+    Field { name, schema, optional: false }
+}
+
+const fn optional(name: &'static str, schema: Schema) -> Field {
+    Field { name, schema, optional: true }
+}
+
+const VIEWS: &[&str] = &[
+    "ideas", "docs", "plan", "epics", "kanban", "tests", "review", "browser", "logs", "commands", "cli-tools", "ai",
+];
+
+const API_KEY: &[Field] = &[
+    field("id", Schema::String),
+    field("provider", Schema::OneOf(&["openai", "google", "anthropic", "xai", "openrouter"])),
+    field("name", Schema::String),
+    field("key", Schema::String),
+    optional("baseURL", Schema::String),
+];
+
+const PLUGIN_API_KEY: &[Field] = &[
+    field("id", Schema::String),
+    field("key", Schema::String),
+    field("name", Schema::String),
+    optional("userId", Schema::String),
+    field("createdAt", Schema::String),
+    optional("lastUsedAt", Schema::String),
+];
+
+const PROJECT_COMMANDS: &[Field] = &[
+    optional("build", Schema::String),
+    optional("dev", Schema::String),
+    optional("run", Schema::String),
+    optional("start", Schema::String),
+    optional("stop", Schema::String),
+];
+
+const PROJECT: &[Field] = &[
+    field("id", Schema::String),
+    field("name", Schema::String),
+    field("path", Schema::String),
+    field("type", Schema::OneOf(&["project", "folder"])),
+    optional("folderConfigId", Schema::String),
+    optional("workflowId", Schema::String),
+    optional("commands", Schema::Record(PROJECT_COMMANDS)),
+    optional("url", Schema::String),
+    optional("autoRun", Schema::Bool),
+    optional("browserPages", Schema::List(&Schema::String)),
+    optional("pluginName", Schema::String),
+    optional("pluginDomain", Schema::String),
+];
+
+const WORKFLOW: &[Field] = &[
+    field("id", Schema::String),
+    field("name", Schema::String),
+    field("items", Schema::List(&Schema::String)),
+];
+
+const SITE_USER: &[Field] = &[
+    field("email", Schema::String),
+    field("name", Schema::String),
+    optional("image", Schema::String),
+];
+
+const USER_SETTINGS: &[Field] = &[
+    field("theme", Schema::OneOf(&["light", "dark", "system"])),
+    field("language", Schema::String),
+    field("notifications", Schema::Bool),
+    field("autoSave", Schema::Bool),
+    field("defaultView", Schema::OneOf(VIEWS)),
+    field("sidebarCollapsed", Schema::Bool),
+    field("editorFontSize", Schema::Number),
+    field("editorFontFamily", Schema::String),
+    field("showLineNumbers", Schema::Bool),
+    field("wordWrap", Schema::Bool),
+    field("aiModel", Schema::String),
+    field("aiProvider", Schema::String),
+    field("apiKeys", Schema::List(&Schema::Record(API_KEY))),
+    optional("pluginApiKeys", Schema::List(&Schema::Record(PLUGIN_API_KEY))),
+    field("projects", Schema::List(&Schema::Record(PROJECT))),
+    field("enabledAgents", Schema::List(&Schema::String)),
+    field("stagehandApiKey", Schema::String),
+    field("openaiApiKey", Schema::String),
+    field("anthropicApiKey", Schema::String),
+    field("googleApiKey", Schema::String),
+    field("grokApiKey", Schema::String),
+    field("workflows", Schema::List(&Schema::Record(WORKFLOW))),
+    optional("activeWorkflow", Schema::String),
+    field("createBranchPerTask", Schema::Bool),
+    field("agentToolSettings", Schema::Any),
+    optional("siteToken", Schema::String),
+    optional("siteUser", Schema::Record(SITE_USER)),
+];
+
+/// Prints the settings, or the one at `key` (dotted, e.g. `projects.0.url`),
+/// with secrets masked unless `reveal`.
+pub async fn get(client: &reqwest::Client, settings: &Settings, key: Option<&str>, reveal: bool) -> anyhow::Result<()> {
+    let mut value = Value::Object(api::fetch_settings(client, &settings.url).await?);
+    if let Some(key) = key {
+        value = lookup(&value, key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No setting {}", key))?;
+    }
+    if !reveal {
+        mask_secrets(&mut value, key.and_then(|k| k.rsplit('.').next()));
+    }
+
+    match (&value, settings.output) {
+        // A single plain value prints as is, for use in scripts
+        (Value::String(s), OutputFormat::Text) => println!("{}", s),
+        (Value::Bool(_) | Value::Number(_) | Value::Null, OutputFormat::Text) => println!("{}", value),
+        _ => println!("{}", serde_json::to_string_pretty(&value)?),
+    }
+    Ok(())
+}
+
+/// Sets the setting at `key` to `value`, which is read as JSON when it parses
+/// (`true`, `14`, `["*"]`) and as a string otherwise.
+pub async fn set(client: &reqwest::Client, settings: &Settings, key: &str, value: &str) -> anyhow::Result<()> {
+    let value: Value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    let current = api::fetch_settings(client, &settings.url).await?;
+
+    let top = key.split('.').next().unwrap_or_default();
+    let mut updated = Value::Object(current);
+    let slot = lookup_mut(&mut updated, key).ok_or_else(|| {
+        anyhow::anyhow!("No setting {}; see `agelum settings get` for the keys", key)
+    })?;
+    *slot = value;
+
+    let mut changes = Map::new();
+    changes.insert(top.to_string(), updated[top].clone());
+    validate(&changes)?;
+    api::update_settings(client, &settings.url, &changes).await?;
+
+    let mut shown = lookup(&updated, key).cloned().unwrap_or_default();
+    mask_secrets(&mut shown, key.rsplit('.').next());
+    println!("✓ Set {} to {}", key, shown);
+    Ok(())
+}
+
+/// Opens the settings as JSON in `$VISUAL`/`$EDITOR`; the keys that changed are
+/// saved once they match the schema.
+pub async fn edit(client: &reqwest::Client, settings: &Settings) -> anyhow::Result<()> {
+    let current = api::fetch_settings(client, &settings.url).await?;
+    let original = serde_json::to_string_pretty(&current)?;
+
+    // Holds secrets, so it's only readable by the user
+    let dir = tempfile::Builder::new().prefix("agelum-settings").tempdir()?;
+    let path = dir.path().join("settings.json");
+    std::fs::write(&path, &original)?;
+
+    let changes = loop {
+        open_editor(&path)?;
+        let content = std::fs::read_to_string(&path)?;
+        if content == original {
+            break None;
+        }
+
+        let parsed = serde_json::from_str::<Map<String, Value>>(&content)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .and_then(|edited| {
+                if let Some(removed) = current.keys().find(|k| !edited.contains_key(*k)) {
+                    anyhow::bail!("{}: settings can't be removed, only changed", removed);
+                }
+                let changes: Map<String, Value> =
+                    edited.into_iter().filter(|(k, v)| current.get(k) != Some(v)).collect();
+                validate(&changes)?;
+                Ok(changes)
+            });
+        match parsed {
+            Ok(changes) => break Some(changes),
+            Err(e) => {
+                eprintln!("Invalid settings: {}", e);
+                if !confirm("Re-open the editor?", true)? {
+                    break None;
+                }
+            }
+        }
+    };
+
+    match changes {
+        Some(changes) if !changes.is_empty() => {
+            api::update_settings(client, &settings.url, &changes).await?;
+            let keys: Vec<&str> = changes.keys().map(String::as_str).collect();
+            println!("✓ Saved {}", keys.join(", "));
+        }
+        _ => println!("No changes saved"),
+    }
+    Ok(())
+}
+
+/// Checks top-level settings against the schema, listing every problem.
+fn validate(changes: &Map<String, Value>) -> anyhow::Result<()> {
+    let mut problems = Vec::new();
+    for (key, value) in changes {
+        match USER_SETTINGS.iter().find(|f| f.name == key) {
+            Some(field) if field.optional && value.is_null() => {}
+            Some(field) => check(&field.schema, value, key, &mut problems),
+            None => problems.push(format!("{}: unknown setting", key)),
+        }
+    }
+    if !problems.is_empty() {
+        anyhow::bail!("{}", problems.join("; "));
+    }
+    Ok(())
+}
+
+fn check(schema: &Schema, value: &Value, path: &str, problems: &mut Vec<String>) {
+    match (schema, value) {
+        (Schema::Any, _) | (Schema::String, Value::String(_)) | (Schema::Bool, Value::Bool(_)) => {}
+        (Schema::Number, Value::Number(n)) => {
+            if n.as_f64().is_some_and(|n| n <= 0.0) {
+                problems.push(format!("{}: must be greater than 0", path));
+            }
+        }
+        (Schema::OneOf(allowed), Value::String(s)) if allowed.contains(&s.as_str()) => {}
+        (Schema::OneOf(allowed), _) => problems.push(format!("{}: expected one of {}", path, allowed.join(", "))),
+        (Schema::List(item), Value::Array(items)) => {
+            for (i, value) in items.iter().enumerate() {
+                check(item, value, &format!("{}.{}", path, i), problems);
+            }
+        }
+        (Schema::Record(fields), Value::Object(map)) => {
+            for field in fields.iter() {
+                match map.get(field.name) {
+                    Some(Value::Null) | None if field.optional => {}
+                    Some(value) => check(&field.schema, value, &format!("{}.{}", path, field.name), problems),
+                    None => problems.push(format!("{}.{}: missing", path, field.name)),
+                }
+            }
+            for key in map.keys().filter(|k| !fields.iter().any(|f| f.name == *k)) {
+                problems.push(format!("{}.{}: unknown field", path, key));
+            }
+        }
+        (schema, _) => {
+            let expected = match schema {
+                Schema::String => "a string",
+                Schema::Bool => "true or false",
+                Schema::Number => "a number",
+                Schema::List(_) => "a list",
+                _ => "an object",
+            };
+            problems.push(format!("{}: expected {}", path, expected));
+        }
+    }
+}
+
+/// Keys whose values are secrets: API keys, tokens and passwords.
+fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key == "key" || key.ends_with("apikey") || key.ends_with("token") || key.contains("secret") || key.contains("password")
+}
+
+/// Masks the string values of secret keys; `key` is the name the value itself
+/// was found under.
+fn mask_secrets(value: &mut Value, key: Option<&str>) {
+    match value {
+        Value::String(s) if key.is_some_and(is_secret) && !s.is_empty() => *s = config::mask(s),
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                mask_secrets(v, Some(k));
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                mask_secrets(item, None);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Value at a dotted path; list items are addressed by index.
+fn lookup<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.').try_fold(value, |value, part| match value {
+        Value::Object(map) => map.get(part),
+        Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Like [`lookup`]; the last part of the path may name a field the object
+/// doesn't have yet, as optional settings are left out until set.
+fn lookup_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    key.split('.').try_fold(value, |value, part| match value {
+        Value::Object(map) => Some(map.entry(part).or_insert(Value::Null)),
+        Value::Array(items) => items.get_mut(part.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn changes(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn accepts_valid_settings() {
+        let valid = json!({
+            "theme": "dark",
+            "editorFontSize": 16,
+            "wordWrap": false,
+            "enabledAgents": ["*"],
+            "pluginApiKeys": null,
+            "apiKeys": [{"id": "a1", "provider": "openai", "name": "main", "key": "sk-1"}],
+        });
+        validate(&changes(valid)).unwrap();
+    }
+
+    #[test]
+    fn lists_every_problem() {
+        let invalid = json!({
+            "theme": "blue",
+            "editorFontSize": 0,
+            "wordWrap": "yes",
+            "colour": "red",
+            "apiKeys": [{"id": "a1", "provider": "openai", "name": "main", "extra": 1}],
+        });
+        let error = validate(&changes(invalid)).unwrap_err().to_string();
+        for problem in [
+            "theme: expected one of light, dark, system",
+            "editorFontSize: must be greater than 0",
+            "wordWrap: expected true or false",
+            "colour: unknown setting",
+            "apiKeys.0.key: missing",
+            "apiKeys.0.extra: unknown field",
+        ] {
+            assert!(error.contains(problem), "{} not in {}", problem, error);
+        }
+    }
+
+    #[test]
+    fn masks_secret_values_at_any_depth() {
+        let mut settings = json!({
+            "theme": "dark",
+            "openaiApiKey": "sk-openai-123456789",
+            "anthropicApiKey": "",
+            "siteToken": "eyJabc.def.ghi",
+            "apiKeys": [{"id": "a1", "name": "main", "key": "sk-live-abcdefghijkl"}],
+            "projects": [{"name": "demo", "path": "/tmp/demo"}],
+        });
+        mask_secrets(&mut settings, None);
+        assert_eq!(settings["theme"], "dark");
+        assert_eq!(settings["openaiApiKey"], "sk-o****");
+        assert_eq!(settings["anthropicApiKey"], "");
+        assert_eq!(settings["siteToken"], "eyJa****");
+        assert_eq!(settings["apiKeys"][0]["key"], "sk-l****");
+        assert_eq!(settings["apiKeys"][0]["name"], "main");
+        assert_eq!(settings["projects"][0]["path"], "/tmp/demo");
+    }
+}
@@ -0,0 +1,149 @@
+use crate::api;
+use crate::compat;
+use crate::config::{OutputFormat, Settings};
+use crate::types::AppStatus;
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    url: String,
+    /// Milliseconds `/api/repositories` took to answer
+    latency_ms: u128,
+    version: Option<String>,
+    cli_version: &'static str,
+    server_mode: bool,
+    base_path: String,
+    repositories: usize,
+    apps: Vec<RunningApp>,
+    problems: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RunningApp {
+    repo: String,
+    #[serde(flatten)]
+    status: AppStatus,
+}
+
+/// Shows whether the server answers, what it runs, the apps it started or found
+/// running, and whether it serves the routes this CLI calls.
+pub async fn execute(client: &reqwest::Client, settings: &Settings) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let repositories = match api::fetch_repository_response(client, &settings.url).await {
+        Ok(repositories) => repositories,
+        Err(e) => {
+            if settings.output == OutputFormat::Text {
+                println!("Server:       {} (unreachable)", settings.url);
+            }
+            return Err(e);
+        }
+    };
+    let latency_ms = started.elapsed().as_millis();
+
+    let mut problems = Vec::new();
+    let version = match api::fetch_version(client, &settings.url).await {
+        Ok(server) => {
+            problems.extend(compat::problems(server.as_ref()));
+            server.map(|s| s.version)
+        }
+        Err(e) => {
+            problems.push(format!("Could not check the server version: {}", e));
+            None
+        }
+    };
+
+    // Asked for all repositories at once; each check may wait on a project's URL
+    let mut checks = tokio::task::JoinSet::new();
+    for repo in &repositories.repositories {
+        let (client, url, name) = (client.clone(), settings.url.clone(), repo.name.clone());
+        checks.spawn(async move {
+            let status = api::fetch_app_status(&client, &url, &name).await;
+            (name, status)
+        });
+    }
+    let mut apps = Vec::new();
+    let mut failed = Vec::new();
+    while let Some(check) = checks.join_next().await {
+        match check? {
+            (repo, Ok(Some(status))) if status.is_running => apps.push(RunningApp { repo, status }),
+            (_, Ok(_)) => {}
+            (repo, Err(_)) => failed.push(repo),
+        }
+    }
+    apps.sort_by(|a, b| a.repo.cmp(&b.repo));
+    if !failed.is_empty() {
+        failed.sort();
+        problems.push(format!("Could not check the apps of {}", failed.join(", ")));
+    }
+
+    let report = Report {
+        url: settings.url.clone(),
+        latency_ms,
+        version,
+        cli_version: compat::CLI_VERSION,
+        server_mode: repositories.server_mode,
+        base_path: repositories.base_path,
+        repositories: repositories.repositories.len(),
+        apps,
+        problems,
+    };
+    if settings.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    print_report(&report);
+    Ok(())
+}
+
+fn print_report(report: &Report) {
+    println!("Server:       {} (answered in {}ms)", report.url, report.latency_ms);
+    println!(
+        "Version:      {} (CLI {})",
+        report.version.as_deref().unwrap_or("unknown"),
+        report.cli_version
+    );
+    println!("Server mode:  {}", if report.server_mode { "yes" } else { "no" });
+    // Empty when the repositories are the projects of the user settings
+    println!(
+        "Base path:    {}",
+        if report.base_path.is_empty() { "- (projects from the user settings)" } else { &report.base_path }
+    );
+    println!("Repositories: {}", report.repositories);
+
+    if report.apps.is_empty() {
+        println!("Running apps: none");
+    } else {
+        println!("Running apps:");
+        let width = report.apps.iter().map(|a| a.repo.len()).max().unwrap_or(0);
+        for app in &report.apps {
+            let status = &app.status;
+            let mut details = vec![if status.is_url_ready { "ready" } else { "starting" }.to_string()];
+            if let Some(pid) = status.pid {
+                details.push(format!("pid {}", pid));
+            }
+            if status.is_managed {
+                if let Some(command) = &status.command {
+                    details.push(format!("`{}`", command));
+                }
+                if let Some(started) = status.started_at.as_deref().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()) {
+                    details.push(format!(
+                        "since {}",
+                        started.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+                    ));
+                }
+            } else {
+                details.push("started outside Agelum".to_string());
+            }
+            println!("  {:<width$}  {}", app.repo, details.join(", "), width = width);
+        }
+    }
+
+    if report.problems.is_empty() {
+        println!("✓ The server serves every route this CLI calls");
+    }
+    for problem in &report.problems {
+        println!("⚠ {}", problem);
+    }
+}
@@ -1,4 +1,5 @@
-use serde_json::json;
+use crate::http::Dispatch;
+use crate::types::TestStep;
 
 pub async fn execute(
     client: &reqwest::Client,
@@ -11,12 +12,11 @@ pub async fn execute(
     let encoded_repo = urlencoding::encode(repo);
     let request_url = format!("{}/api/tests/{}/steps?repo={}", url, test_id, encoded_repo);
     
-    let body = json!({
-        "command": command,
-        "args": args,
-    });
+    let mut words = vec![command.to_string()];
+    words.extend(args);
+    let step = TestStep::from_args(&words)?;
 
-    let resp = client.post(request_url).json(&body).send().await?;
+    let resp = client.post(request_url).json(&step).dispatch().await?;
 
     if !resp.status().is_success() {
         eprintln!("Error adding test step: {}", resp.status());
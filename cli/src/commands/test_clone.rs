@@ -0,0 +1,52 @@
+use crate::api;
+use crate::types::Test;
+
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    name: &str,
+    group: Option<String>,
+) -> anyhow::Result<()> {
+    let tests = api::fetch_tests(client, url, repo).await?;
+    let source = tests
+        .iter()
+        .find(|t| t.id == test_id)
+        .ok_or_else(|| anyhow::anyhow!("Test not found: {}", test_id))?;
+    // Saving steps rewrites test.json without metadata, so group and
+    // description come from the index; steps and browser settings from test.json
+    let definition = api::fetch_test_definition(client, url, repo, test_id).await?;
+
+    let group = group.or_else(|| source.group.clone());
+    let folder = api::test_folder_name(name);
+    // The server would silently overwrite an existing test in the same folder
+    if let Some(existing) = tests
+        .iter()
+        .find(|t| t.group == group && t.folder.as_deref() == Some(folder.as_str()))
+    {
+        anyhow::bail!(
+            "Test {} already uses folder {}; choose another --name",
+            existing.id,
+            folder
+        );
+    }
+
+    let draft = Test {
+        name: name.to_string(),
+        group,
+        description: source.description.clone(),
+        folder: None,
+        ..definition
+    };
+    let created = api::create_test(client, url, repo, &draft).await?;
+
+    println!(
+        "✓ Cloned {} into {} ({}) with {} steps",
+        test_id,
+        created.name,
+        created.id,
+        draft.steps.len()
+    );
+    Ok(())
+}
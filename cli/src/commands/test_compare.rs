@@ -0,0 +1,348 @@
+use crate::api;
+use crate::types::{StepLog, TestExecution};
+
+const STEP_WIDTH: usize = 40;
+
+/// One line of the comparison: a step found in both runs, or in only one of them.
+enum Aligned<'a> {
+    Both(&'a StepLog, &'a StepLog),
+    Removed(&'a StepLog),
+    Added(&'a StepLog),
+}
+
+impl Aligned<'_> {
+    /// The step of the entry, from run A when it is in both.
+    fn step(&self) -> &StepLog {
+        match self {
+            Aligned::Both(a, _) | Aligned::Removed(a) | Aligned::Added(a) => a,
+        }
+    }
+}
+
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    base: &str,
+    head: &str,
+) -> anyhow::Result<()> {
+    let repository = api::fetch_repository(client, url, repo).await?;
+    let executions = api::fetch_executions(client, url, repo, test_id).await?;
+    let base_exec = api::find_execution(&executions, base)?;
+    let head_exec = api::find_execution(&executions, head)?;
+
+    println!("Comparing {} of {}", base_exec.id, test_id);
+    println!("     with {}", head_exec.id);
+    println!();
+    println!("  {:<w$} {:<18} {:<18}", "", "A", "B", w = STEP_WIDTH);
+    println!(
+        "  {:<w$} {:<18} {:<18}",
+        "Status",
+        base_exec.status,
+        head_exec.status,
+        w = STEP_WIDTH
+    );
+    println!(
+        "  {:<w$} {:<18} {:<18}",
+        "Duration",
+        base_exec.duration.map(format_ms).unwrap_or_else(|| "-".to_string()),
+        head_exec.duration.map(format_ms).unwrap_or_else(|| "-".to_string()),
+        w = STEP_WIDTH
+    );
+    print_error_change(base_exec, head_exec);
+
+    // Step logs are only recorded by local runs
+    let base_log = api::read_execution_log(&repository, test_id, &base_exec.id);
+    let head_log = api::read_execution_log(&repository, test_id, &head_exec.id);
+    let (base_log, head_log) = match (base_log, head_log) {
+        (Ok(base_log), Ok(head_log)) => (base_log, head_log),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("\nNo step comparison: {}", e);
+            return Ok(());
+        }
+    };
+
+    println!();
+    let aligned = align(&base_log.steps, &head_log.steps);
+    let mut row = None;
+    for (entry, (a_stopped, b_stopped)) in aligned.iter().zip(stopped(&aligned)) {
+        if entry.step().row != row {
+            row = entry.step().row;
+            if let Some(row) = row {
+                println!("  Row {}", row);
+            }
+        }
+        print_entry(entry, a_stopped, b_stopped);
+    }
+
+    let changed = aligned
+        .iter()
+        .filter(|e| matches!(e, Aligned::Both(a, b) if a.status != b.status))
+        .count();
+    println!("\n{} steps changed status", changed);
+    Ok(())
+}
+
+fn print_error_change(base: &TestExecution, head: &TestExecution) {
+    if base.error == head.error {
+        return;
+    }
+    if let Some(error) = &base.error {
+        println!("  - error: {}", error);
+    }
+    if let Some(error) = &head.error {
+        println!("  + error: {}", error);
+    }
+}
+
+fn print_entry(entry: &Aligned, a_stopped: bool, b_stopped: bool) {
+    match entry {
+        Aligned::Both(a, b) => {
+            let marker = if a.status != b.status { "!" } else { " " };
+            println!(
+                "{} {:<w$} {:<18} {:<18}{}",
+                marker,
+                step_title(a),
+                format_step(a),
+                format_step(b),
+                format_delta(a.duration, b.duration),
+                w = STEP_WIDTH
+            );
+            if a.error != b.error {
+                if let Some(error) = &a.error {
+                    print_lines("- error: ", error);
+                }
+                if let Some(error) = &b.error {
+                    print_lines("+ error: ", error);
+                }
+            }
+            print_output_diff("stdout", &a.stdout, &b.stdout);
+            print_output_diff("stderr", &a.stderr, &b.stderr);
+        }
+        Aligned::Removed(a) => println!(
+            "- {:<w$} {:<18} {:<18}{}",
+            step_title(a),
+            format_step(a),
+            "-",
+            if b_stopped { "(not run in B)" } else { "(removed)" },
+            w = STEP_WIDTH
+        ),
+        Aligned::Added(b) => println!(
+            "+ {:<w$} {:<18} {:<18}{}",
+            step_title(b),
+            "-",
+            format_step(b),
+            if a_stopped { "(not run in A)" } else { "(added)" },
+            w = STEP_WIDTH
+        ),
+    }
+}
+
+/// Prints the lines only one of the runs printed.
+fn print_output_diff(stream: &str, a: &str, b: &str) {
+    if a == b {
+        return;
+    }
+    let a_lines: Vec<&str> = a.lines().filter(|l| !l.trim().is_empty()).collect();
+    let b_lines: Vec<&str> = b.lines().filter(|l| !l.trim().is_empty()).collect();
+    for line in a_lines.iter().filter(|l| !b_lines.contains(l)) {
+        println!("      - {}: {}", stream, line);
+    }
+    for line in b_lines.iter().filter(|l| !a_lines.contains(l)) {
+        println!("      + {}: {}", stream, line);
+    }
+}
+
+fn print_lines(prefix: &str, text: &str) {
+    for line in text.lines() {
+        println!("      {}{}", prefix, line);
+    }
+}
+
+/// Aligns the steps of two runs by longest common subsequence of (row, step), so
+/// inserted or removed steps don't shift the rest of the comparison.
+fn align<'a>(a: &'a [StepLog], b: &'a [StepLog]) -> Vec<Aligned<'a>> {
+    let same = |x: &StepLog, y: &StepLog| x.row == y.row && x.step == y.step;
+
+    // lengths[i][j] = LCS length of a[i..] and b[j..]
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if same(&a[i], &b[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut aligned = Vec::new();
+    while i < a.len() && j < b.len() {
+        if same(&a[i], &b[j]) {
+            aligned.push(Aligned::Both(&a[i], &b[j]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            aligned.push(Aligned::Removed(&a[i]));
+            i += 1;
+        } else {
+            aligned.push(Aligned::Added(&b[j]));
+            j += 1;
+        }
+    }
+    aligned.extend(a[i..].iter().map(Aligned::Removed));
+    aligned.extend(b[j..].iter().map(Aligned::Added));
+    aligned
+}
+
+/// Whether each run had stopped before each entry. A run stops at its first
+/// failing step, so later steps missing from it weren't removed, they just
+/// didn't run. Every data row starts afresh.
+fn stopped(aligned: &[Aligned]) -> Vec<(bool, bool)> {
+    let mut row = None;
+    let (mut a_stopped, mut b_stopped) = (false, false);
+    aligned
+        .iter()
+        .map(|entry| {
+            if entry.step().row != row {
+                row = entry.step().row;
+                (a_stopped, b_stopped) = (false, false);
+            }
+            let before = (a_stopped, b_stopped);
+            match entry {
+                Aligned::Both(a, b) => {
+                    a_stopped |= a.status != "passed";
+                    b_stopped |= b.status != "passed";
+                }
+                Aligned::Removed(a) => a_stopped |= a.status != "passed",
+                Aligned::Added(b) => b_stopped |= b.status != "passed",
+            }
+            before
+        })
+        .collect()
+}
+
+fn step_title(step: &StepLog) -> String {
+    let title = format!("{}: {}", step.label, step.step);
+    if title.chars().count() > STEP_WIDTH {
+        let truncated: String = title.chars().take(STEP_WIDTH - 1).collect();
+        format!("{}…", truncated)
+    } else {
+        title
+    }
+}
+
+fn format_step(step: &StepLog) -> String {
+    let mark = match step.status.as_str() {
+        "passed" => "✓",
+        "timedOut" => "⏱",
+        _ => "✗",
+    };
+    format!("{} {}", mark, format_ms(step.duration))
+}
+
+fn format_delta(a: u64, b: u64) -> String {
+    let delta = b as i64 - a as i64;
+    // Small differences are noise
+    if delta.unsigned_abs() < 100 {
+        return String::new();
+    }
+    let sign = if delta > 0 { "+" } else { "-" };
+    format!("{}{}", sign, format_ms(delta.unsigned_abs()))
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(steps: &[(&str, &str)]) -> Vec<StepLog> {
+        run_rows(&steps.iter().map(|(step, status)| (None, *step, *status)).collect::<Vec<_>>())
+    }
+
+    fn run_rows(steps: &[(Option<usize>, &str, &str)]) -> Vec<StepLog> {
+        steps
+            .iter()
+            .enumerate()
+            .map(|(i, (row, step, status))| StepLog {
+                label: format!("Step {}", i + 1),
+                step: step.to_string(),
+                row: *row,
+                status: status.to_string(),
+                duration: 100,
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: None,
+                error: None,
+            })
+            .collect()
+    }
+
+    /// The alignment as "= step" (both), "- step" (only A) and "+ step" (only B).
+    fn shape(aligned: &[Aligned]) -> Vec<String> {
+        aligned
+            .iter()
+            .map(|entry| match entry {
+                Aligned::Both(a, _) => format!("= {}", a.step),
+                Aligned::Removed(a) => format!("- {}", a.step),
+                Aligned::Added(b) => format!("+ {}", b.step),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aligns_inserted_and_removed_steps() {
+        let a = run(&[("open /", "passed"), ("click @e1", "passed"), ("assert title Home", "passed")]);
+        let b = run(&[("open /", "passed"), ("fill @e2 me", "passed"), ("click @e1", "passed"), ("assert title Home", "passed")]);
+        assert_eq!(shape(&align(&a, &b)), ["= open /", "+ fill @e2 me", "= click @e1", "= assert title Home"]);
+        assert_eq!(shape(&align(&b, &a)), ["= open /", "- fill @e2 me", "= click @e1", "= assert title Home"]);
+        assert!(stopped(&align(&a, &b)).iter().all(|&flags| flags == (false, false)));
+    }
+
+    #[test]
+    fn aligns_reordered_steps() {
+        let a = run(&[("open /", "passed"), ("click @e1", "passed"), ("fill @e2 me", "passed")]);
+        let b = run(&[("open /", "passed"), ("fill @e2 me", "passed"), ("click @e1", "passed")]);
+        assert_eq!(shape(&align(&a, &b)), ["= open /", "- click @e1", "= fill @e2 me", "+ click @e1"]);
+    }
+
+    #[test]
+    fn marks_steps_after_a_failure_as_not_run() {
+        let a = run(&[("open /", "passed"), ("click @e1", "failed")]);
+        let b = run(&[("open /", "passed"), ("click @e1", "passed"), ("assert title Home", "passed")]);
+        let aligned = align(&a, &b);
+        assert_eq!(shape(&aligned), ["= open /", "= click @e1", "+ assert title Home"]);
+        assert_eq!(stopped(&aligned), [(false, false), (false, false), (true, false)]);
+
+        // Timed out in B: the steps only A ran were not removed
+        let b = run(&[("open /", "timedOut")]);
+        let aligned = align(&a, &b);
+        assert_eq!(shape(&aligned), ["= open /", "- click @e1"]);
+        assert_eq!(stopped(&aligned)[1], (false, true));
+    }
+
+    #[test]
+    fn every_row_starts_afresh() {
+        let a = run_rows(&[(Some(1), "open /", "failed"), (Some(2), "open /", "passed"), (Some(2), "click @e1", "passed")]);
+        let b = run_rows(&[(Some(1), "open /", "passed"), (Some(1), "click @e1", "passed"), (Some(2), "open /", "passed"), (Some(2), "click @e1", "passed")]);
+        let aligned = align(&a, &b);
+        assert_eq!(shape(&aligned), ["= open /", "+ click @e1", "= open /", "= click @e1"]);
+        assert_eq!(stopped(&aligned), [(false, false), (true, false), (false, false), (false, false)]);
+    }
+
+    #[test]
+    fn aligns_empty_runs() {
+        let b = run(&[("open /", "passed")]);
+        assert_eq!(shape(&align(&[], &b)), ["+ open /"]);
+        assert!(align(&[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::api;
+use crate::types::TestExecution;
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Folder inside the test folder holding the approved screenshots.
+const BASELINE_DIR: &str = "baseline";
+
+/// Per-channel difference ignored by the pixel metric (absorbs anti-aliasing noise).
+const PIXEL_TOLERANCE: u8 = 10;
+
+/// Maximum YIQ distance between two colors, and the share of it that counts as visible.
+const MAX_YIQ_DELTA: f64 = 35215.0;
+const PERCEPTUAL_TOLERANCE: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy)]
+pub enum DiffMetric {
+    Pixel,
+    Perceptual,
+}
+
+impl std::fmt::Display for DiffMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffMetric::Pixel => write!(f, "pixel"),
+            DiffMetric::Perceptual => write!(f, "perceptual"),
+        }
+    }
+}
+
+impl std::str::FromStr for DiffMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pixel" => Ok(DiffMetric::Pixel),
+            "perceptual" => Ok(DiffMetric::Perceptual),
+            _ => Err(format!("Invalid diff metric: {}", s)),
+        }
+    }
+}
+
+struct Screenshot {
+    label: String,
+    bytes: Vec<u8>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    base: &str,
+    head: &str,
+    threshold: f64,
+    metric: DiffMetric,
+    out: Option<PathBuf>,
+    approve: bool,
+) -> anyhow::Result<()> {
+    let repository = api::fetch_repository(client, url, repo).await?;
+    let test = api::fetch_test(client, url, repo, test_id).await?;
+    // Baselines are files in the test folder, so they need it on this machine
+    let baseline_dir = api::test_dir(&repository, &test).map(|dir| dir.join(BASELINE_DIR));
+    let baseline = || baseline_dir.as_ref().map_err(|e| anyhow::anyhow!("Can't use the baseline: {}", e));
+    if approve {
+        baseline()?;
+    }
+    let executions = api::fetch_executions(client, url, repo, test_id).await?;
+
+    let head_exec = api::find_execution(&executions, head)?;
+    let head_shots = download_screenshots(client, url, head_exec).await?;
+    let base_shots = if base == BASELINE_DIR {
+        read_baseline(baseline()?)?
+    } else {
+        download_screenshots(client, url, api::find_execution(&executions, base)?).await?
+    };
+
+    let out_dir = match out {
+        Some(out) => out,
+        None => api::runs_dir(&repository, test_id)
+            .ok_or_else(|| anyhow::anyhow!("{} is on another machine; pass --out for the diff images", repo))?
+            .join(&head_exec.id)
+            .join(format!("diff-{}", base)),
+    };
+    std::fs::create_dir_all(&out_dir)?;
+
+    println!(
+        "Comparing {} screenshots: {} → {} (metric: {}, threshold: {:.2}%)",
+        test_id,
+        base,
+        head_exec.id,
+        metric,
+        threshold * 100.0
+    );
+
+    let mut failures = 0;
+    let steps = base_shots.len().max(head_shots.len());
+    for i in 0..steps {
+        let (base_shot, head_shot) = (base_shots.get(i), head_shots.get(i));
+        let label = head_shot.or(base_shot).map(|s| s.label.as_str()).unwrap_or_default();
+
+        let (Some(base_shot), Some(head_shot)) = (base_shot, head_shot) else {
+            let side = if head_shot.is_some() { "head" } else { "base" };
+            println!("  {:02} {:<30} only in {} ✗", i + 1, label, side);
+            failures += 1;
+            continue;
+        };
+
+        let base_img = image::load_from_memory(&base_shot.bytes)?.to_rgba8();
+        let head_img = image::load_from_memory(&head_shot.bytes)?.to_rgba8();
+        let (score, diff) = diff_images(&base_img, &head_img, metric);
+
+        if score == 0.0 {
+            println!("  {:02} {:<30} {:>7.2}%", i + 1, label, 0.0);
+            continue;
+        }
+
+        let diff_path = out_dir.join(format!("{:02}-{}.png", i + 1, label));
+        diff.save(&diff_path)?;
+
+        let failed = exceeds(score, threshold);
+        if failed {
+            failures += 1;
+        }
+        println!(
+            "  {:02} {:<30} {:>7.2}% {} diff: {}",
+            i + 1,
+            label,
+            score * 100.0,
+            if failed { "✗" } else { "✓" },
+            diff_path.display()
+        );
+    }
+
+    if approve {
+        let baseline_dir = baseline()?;
+        write_baseline(baseline_dir, &head_shots)?;
+        println!(
+            "✓ Approved {} screenshots from {} as baseline in {}",
+            head_shots.len(),
+            head_exec.id,
+            baseline_dir.display()
+        );
+        return Ok(());
+    }
+
+    if failures > 0 {
+        anyhow::bail!(
+            "{} of {} screenshots differ beyond the {:.2}% threshold",
+            failures,
+            steps,
+            threshold * 100.0
+        );
+    }
+
+    println!("✓ No visual differences beyond threshold");
+    Ok(())
+}
+
+/// Screenshots are named `<timestamp>-<name>.png` by the test engine; the name is
+/// kept as a label so pairs stay readable in the report.
+fn screenshot_label(path: &str) -> String {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let stem = file.strip_suffix(".png").unwrap_or(file);
+    match stem.split_once('-') {
+        Some((prefix, rest)) if prefix.chars().all(|c| c.is_ascii_digit()) => rest.to_string(),
+        _ => stem.to_string(),
+    }
+}
+
+async fn download_screenshots(
+    client: &reqwest::Client,
+    url: &str,
+    execution: &TestExecution,
+) -> anyhow::Result<Vec<Screenshot>> {
+    let mut shots = Vec::new();
+    for path in &execution.screenshots {
+        shots.push(Screenshot {
+            label: screenshot_label(path),
+            bytes: api::fetch_artifact(client, url, path).await?,
+        });
+    }
+    Ok(shots)
+}
+
+fn read_baseline(dir: &Path) -> anyhow::Result<Vec<Screenshot>> {
+    if !dir.exists() {
+        anyhow::bail!(
+            "No approved baseline in {}. Approve one with --approve or pass --base <execution>",
+            dir.display()
+        );
+    }
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.to_string_lossy().to_string();
+            Ok(Screenshot {
+                label: screenshot_label(&name),
+                bytes: std::fs::read(&path)?,
+            })
+        })
+        .collect()
+}
+
+fn write_baseline(dir: &Path, shots: &[Screenshot]) -> anyhow::Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)?;
+    for (i, shot) in shots.iter().enumerate() {
+        std::fs::write(dir.join(format!("{:02}-{}.png", i + 1, shot.label)), &shot.bytes)?;
+    }
+    Ok(())
+}
+
+/// Returns the fraction of differing pixels and an image of `head` faded out with
+/// the differing pixels highlighted in red. Size mismatches count as differences.
+fn diff_images(base: &RgbaImage, head: &RgbaImage, metric: DiffMetric) -> (f64, RgbaImage) {
+    let width = base.width().max(head.width());
+    let height = base.height().max(head.height());
+    let mut diff = RgbaImage::new(width, height);
+    let mut differing = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let a = base.get_pixel_checked(x, y);
+            let b = head.get_pixel_checked(x, y);
+            let changed = match (a, b) {
+                (Some(a), Some(b)) => pixels_differ(a, b, metric),
+                _ => true,
+            };
+
+            let out = if changed {
+                differing += 1;
+                Rgba([255, 0, 0, 255])
+            } else {
+                let l = luma(b.or(a).copied().unwrap_or(Rgba([255, 255, 255, 255])));
+                let faded = 255 - (255 - l as u8) / 4;
+                Rgba([faded, faded, faded, 255])
+            };
+            diff.put_pixel(x, y, out);
+        }
+    }
+
+    let total = u64::from(width) * u64::from(height);
+    let score = if total == 0 { 0.0 } else { differing as f64 / total as f64 };
+    (score, diff)
+}
+
+/// Whether a share of differing pixels fails the comparison; a share equal to
+/// the threshold is still accepted.
+fn exceeds(score: f64, threshold: f64) -> bool {
+    score > threshold
+}
+
+fn pixels_differ(a: &Rgba<u8>, b: &Rgba<u8>, metric: DiffMetric) -> bool {
+    match metric {
+        DiffMetric::Pixel => a
+            .0
+            .iter()
+            .zip(b.0.iter())
+            .any(|(ca, cb)| ca.abs_diff(*cb) > PIXEL_TOLERANCE),
+        DiffMetric::Perceptual => {
+            yiq_delta(*a, *b) > MAX_YIQ_DELTA * PERCEPTUAL_TOLERANCE * PERCEPTUAL_TOLERANCE
+        }
+    }
+}
+
+/// Color channels blended over a white background.
+fn blend(p: Rgba<u8>) -> (f64, f64, f64) {
+    let alpha = f64::from(p[3]) / 255.0;
+    let blend = |c: u8| 255.0 + (f64::from(c) - 255.0) * alpha;
+    (blend(p[0]), blend(p[1]), blend(p[2]))
+}
+
+fn luma(p: Rgba<u8>) -> f64 {
+    let (r, g, b) = blend(p);
+    r * 0.299 + g * 0.587 + b * 0.114
+}
+
+/// Squared distance in YIQ space, weighted towards brightness as in pixelmatch.
+fn yiq_delta(a: Rgba<u8>, b: Rgba<u8>) -> f64 {
+    let (r1, g1, b1) = blend(a);
+    let (r2, g2, b2) = blend(b);
+    let (dr, dg, db) = (r1 - r2, g1 - g2, b1 - b2);
+
+    let y = dr * 0.298_895_31 + dg * 0.586_622_47 + db * 0.114_482_23;
+    let i = dr * 0.595_977_99 - dg * 0.274_176_10 - db * 0.321_801_89;
+    let q = dr * 0.211_470_17 - dg * 0.522_617_11 + db * 0.311_146_94;
+
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    /// A white 10x10 image with the first `changed` pixels set to `color`.
+    fn image(changed: u32, color: Rgba<u8>) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(10, 10, WHITE);
+        for i in 0..changed {
+            image.put_pixel(i % 10, i / 10, color);
+        }
+        image
+    }
+
+    #[test]
+    fn scores_the_share_of_differing_pixels() {
+        let base = image(0, WHITE);
+        let (score, diff) = diff_images(&base, &image(3, Rgba([0, 0, 0, 255])), DiffMetric::Pixel);
+        assert_eq!(score, 0.03);
+        assert_eq!(*diff.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_ne!(*diff.get_pixel(5, 5), Rgba([255, 0, 0, 255]));
+
+        assert_eq!(diff_images(&base, &base, DiffMetric::Perceptual).0, 0.0);
+    }
+
+    #[test]
+    fn fails_only_beyond_the_threshold() {
+        let base = image(0, WHITE);
+        let (score, _) = diff_images(&base, &image(5, Rgba([0, 0, 0, 255])), DiffMetric::Pixel);
+        assert!(!exceeds(score, 0.05));
+        assert!(!exceeds(score, 0.1));
+        assert!(exceeds(score, 0.04));
+    }
+
+    #[test]
+    fn tolerates_small_color_shifts() {
+        let base = image(0, WHITE);
+        let (score, _) = diff_images(&base, &image(100, Rgba([250, 250, 250, 255])), DiffMetric::Pixel);
+        assert_eq!(score, 0.0);
+
+        // Too faint to see, though beyond the pixel tolerance
+        let faint = image(100, Rgba([240, 240, 240, 255]));
+        assert_eq!(diff_images(&base, &faint, DiffMetric::Pixel).0, 1.0);
+        assert_eq!(diff_images(&base, &faint, DiffMetric::Perceptual).0, 0.0);
+    }
+
+    #[test]
+    fn size_mismatches_count_as_differences() {
+        let base = image(0, WHITE);
+        let wider = RgbaImage::from_pixel(20, 10, WHITE);
+        assert_eq!(diff_images(&base, &wider, DiffMetric::Pixel).0, 0.5);
+    }
+
+    #[test]
+    fn yiq_delta_weighs_brightness_and_transparency() {
+        assert_eq!(yiq_delta(WHITE, WHITE), 0.0);
+        let black = Rgba([0, 0, 0, 255]);
+        let delta = yiq_delta(WHITE, black);
+        assert!(delta > MAX_YIQ_DELTA * 0.9 && delta <= MAX_YIQ_DELTA, "{}", delta);
+        // Transparent pixels are blended over white
+        assert_eq!(yiq_delta(WHITE, Rgba([0, 0, 0, 0])), 0.0);
+        // A change of brightness shows more than one of hue at the same distance
+        assert!(yiq_delta(WHITE, Rgba([200, 200, 200, 255])) > yiq_delta(WHITE, Rgba([255, 200, 255, 255])));
+    }
+}
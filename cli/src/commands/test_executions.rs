@@ -1,4 +1,5 @@
-use crate::types::TestExecutionResponse;
+use crate::http::Dispatch;
+use crate::types::TestExecution;
 
 pub async fn execute(
     client: &reqwest::Client,
@@ -13,7 +14,7 @@ pub async fn execute(
         url, test_id, encoded_repo, last
     );
     
-    let resp = client.get(request_url).send().await?;
+    let resp = client.get(request_url).dispatch().await?;
 
     if !resp.status().is_success() {
         eprintln!("Error fetching test executions: {}", resp.status());
@@ -23,17 +24,19 @@ pub async fn execute(
         return Ok(());
     }
 
-    let exec_resp: TestExecutionResponse = resp.json().await?;
+    // A bare array of the runs' result.json, most recent first; the server
+    // ignores `last`, so it's applied here
+    let executions: Vec<TestExecution> = resp.json().await?;
     
-    if exec_resp.executions.is_empty() {
+    if executions.is_empty() {
         println!("No executions found for test {}", test_id);
     } else {
         println!("Test Executions for {}:", test_id);
-        for exec in exec_resp.executions {
+        for exec in executions.into_iter().take(last) {
             println!(
                 "  [{}/{}] {} - Status: {}{}",
                 exec.id,
-                exec.timestamp,
+                exec.started_at,
                 exec.test_id,
                 exec.status,
                 exec.error.as_ref().map(|e| format!(" (Error: {})", e)).unwrap_or_default()
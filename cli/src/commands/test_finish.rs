@@ -1,3 +1,4 @@
+use crate::http::Dispatch;
 use serde_json::json;
 
 pub async fn execute(
@@ -16,7 +17,7 @@ pub async fn execute(
         "error": error,
     });
 
-    let resp = client.post(request_url).json(&body).send().await?;
+    let resp = client.post(request_url).json(&body).dispatch().await?;
 
     if !resp.status().is_success() {
         eprintln!("Error finishing test: {}", resp.status());
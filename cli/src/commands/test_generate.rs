@@ -0,0 +1,156 @@
+use crate::api;
+use crate::commands::test_record;
+use crate::interactive;
+use crate::types::{StepAction, Test, TestStep};
+
+/// Command the AI answers with once the goal is reached.
+const DONE: &str = "done";
+/// Failed suggestions in a row before giving up.
+const MAX_FAILURES: usize = 3;
+
+/// Generates a test from a natural-language goal: the recording AI is asked for
+/// one step at a time, each step runs in the recording browser, and the steps are
+/// saved as a new test once the user accepts them.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    name: &str,
+    start_url: &str,
+    goal: &str,
+    group: Option<String>,
+    backend: Option<String>,
+    max_steps: usize,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let tests = api::fetch_tests(client, url, repo).await?;
+    let folder = api::test_folder_name(name);
+    // The server would silently overwrite an existing test in the same folder,
+    // of the group it puts the test in
+    let server_group = group.as_deref().unwrap_or(api::DEFAULT_TEST_GROUP);
+    if let Some(existing) = tests
+        .iter()
+        .find(|t| t.group.as_deref() == Some(server_group) && t.folder.as_deref() == Some(folder.as_str()))
+    {
+        anyhow::bail!("Test {} already uses folder {}; choose another --name", existing.id, folder);
+    }
+
+    let Some(backend) = test_record::find_backend(client, url, backend).await? else {
+        anyhow::bail!("No AI backend available for generating steps");
+    };
+    let project_path = api::fetch_repository(client, url, repo).await.ok().map(|r| r.path);
+    println!("Generating steps with {} ({})", backend.label, backend.model);
+
+    let open = vec!["open".to_string(), start_url.to_string()];
+    println!("  > {}", shell_words::join(&open));
+    let mut steps = vec![test_record::record_command(client, url, &open).await?];
+
+    let mut failures: Vec<String> = Vec::new();
+    let mut finished = false;
+    while steps.len() < max_steps {
+        let capture = api::record_capture(client, url).await?;
+        let prompt = next_step_prompt(goal, &steps, failures.last().map(String::as_str));
+        let recommendation =
+            api::record_ai(client, url, &backend.id, &prompt, &capture, project_path.as_deref()).await?;
+        let step_name = Some(recommendation.step_description).filter(|d| !d.is_empty());
+
+        if recommendation.kind == "prompt" {
+            // The agent carried out the rest of the goal itself; keep it as a prompt step
+            println!("  The AI ran the rest of the goal itself; it becomes a prompt step");
+            steps.push(TestStep {
+                id: None,
+                name: step_name,
+                timeout: None,
+                action: StepAction::Prompt { instruction: goal.to_string() },
+            });
+            finished = true;
+            break;
+        }
+        if recommendation.command.is_empty() || recommendation.command == DONE {
+            finished = true;
+            break;
+        }
+
+        let mut words = vec![recommendation.command];
+        words.extend(recommendation.args);
+        let command = shell_words::join(&words);
+        println!("  > {}", command);
+        if !recommendation.explanation.is_empty() {
+            println!("    {}", recommendation.explanation);
+        }
+        if steps.last().and_then(|s| s.browser_args()).as_ref() == Some(&words) {
+            eprintln!("  The AI repeated the last step; stopping");
+            break;
+        }
+
+        match test_record::record_command(client, url, &words).await {
+            Ok(mut step) => {
+                step.name = step_name;
+                steps.push(step);
+                failures.clear();
+            }
+            Err(e) => {
+                eprintln!("  Error: {}", e);
+                failures.push(format!("`{}` failed: {}", command, e));
+                if failures.len() >= MAX_FAILURES {
+                    anyhow::bail!("Giving up after {} failed suggestions in a row", MAX_FAILURES);
+                }
+            }
+        }
+    }
+    if !finished {
+        eprintln!("⚠ Stopped after {} steps; the goal may not be reached yet", steps.len());
+    }
+
+    println!("\nProposed steps for {}:", name);
+    for (i, step) in steps.iter().enumerate() {
+        match &step.name {
+            Some(step_name) => println!("  {}) {}  # {}", i + 1, step, step_name),
+            None => println!("  {}) {}", i + 1, step),
+        }
+    }
+    println!();
+    if !yes && !interactive::confirm("Create the test with these steps?", true)? {
+        println!("No test created");
+        return Ok(());
+    }
+
+    let draft = Test {
+        id: String::new(),
+        name: name.to_string(),
+        group,
+        description: Some(goal.to_string()),
+        folder: None,
+        steps: Vec::new(),
+        browser: None,
+    };
+    let created = api::create_test(client, url, repo, &draft).await?;
+    if let Err(e) = api::save_test_steps(client, url, repo, &created.id, &steps).await {
+        // Don't leave an empty test behind
+        if let Err(delete_error) = api::delete_test(client, url, repo, &created.id).await {
+            eprintln!("⚠ Could not delete the empty test {}: {}", created.id, delete_error);
+        }
+        anyhow::bail!("Could not save the steps of {}: {}", created.name, e);
+    }
+    println!("✓ Created test {} ({}) with {} steps", created.name, created.id, steps.len());
+    Ok(())
+}
+
+/// Instruction for the next step, with the steps so far so the AI can tell
+/// where it is, and the last failed suggestion so it doesn't repeat it.
+fn next_step_prompt(goal: &str, steps: &[TestStep], last_failure: Option<&str>) -> String {
+    let mut prompt = format!("Goal of the test: {}\n\nSteps done so far:\n", goal);
+    for (i, step) in steps.iter().enumerate() {
+        prompt.push_str(&format!("{}) {}\n", i + 1, step));
+    }
+    if let Some(failure) = last_failure {
+        prompt.push_str(&format!("\nThe last suggestion {}. Suggest something else.\n", failure));
+    }
+    prompt.push_str(&format!(
+        "\nGive the single next step towards the goal on the current page. \
+         If the goal is already reached, answer with the command \"{}\".",
+        DONE
+    ));
+    prompt
+}
@@ -0,0 +1,66 @@
+use crate::api;
+use crate::types::StepLog;
+
+/// Replays the step log of a local run.
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    execution: &str,
+) -> anyhow::Result<()> {
+    let repository = api::fetch_repository(client, url, repo).await?;
+    let executions = api::fetch_executions(client, url, repo, test_id).await?;
+    let execution_id = &api::find_execution(&executions, execution)?.id;
+    let log = api::read_execution_log(&repository, test_id, execution_id)?;
+
+    println!(
+        "Execution {} of {}: {} (started {})",
+        log.execution_id, log.test_id, log.status, log.started_at
+    );
+
+    let mut row = None;
+    for step in &log.steps {
+        if step.row != row {
+            row = step.row;
+            if let Some(row) = row {
+                println!("\n▶ Row {}", row);
+            }
+        }
+        print_step(step);
+    }
+    Ok(())
+}
+
+fn print_step(step: &StepLog) {
+    for line in step_lines(step) {
+        println!("{}", line);
+    }
+}
+
+/// A step of the log as printed: the step with its outcome, then its output.
+pub fn step_lines(step: &StepLog) -> Vec<String> {
+    let mark = match step.status.as_str() {
+        "passed" => "✓",
+        "timedOut" => "⏱",
+        _ => "✗",
+    };
+    let exit = step
+        .exit_code
+        .map(|code| format!(", exit {}", code))
+        .unwrap_or_default();
+    let mut lines = vec![format!("  {} {}: {} ({}ms{})", mark, step.label, step.step, step.duration, exit)];
+
+    for line in step.stdout.lines().filter(|l| !l.trim().is_empty()) {
+        lines.push(format!("      {}", line));
+    }
+    for line in step.stderr.lines().filter(|l| !l.trim().is_empty()) {
+        lines.push(format!("      stderr: {}", line));
+    }
+    if let Some(error) = &step.error {
+        for line in error.lines() {
+            lines.push(format!("      {}", line));
+        }
+    }
+    lines
+}
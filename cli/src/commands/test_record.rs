@@ -0,0 +1,216 @@
+use crate::api;
+use crate::types::{AIBackend, RecordCapture, StepAction, TestStep};
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+Type an agent-browser command to run it and record it as a step, e.g.
+  open https://example.com
+  click @e2
+  fill @e3 \"hello world\"
+Other input:
+  ai <instruction>       let the AI pick the next step from the current page
+  prompt <instruction>   record a prompt step without running it
+  assert <check> <value> record an assertion (text, element, url or title)
+  :snapshot              print the current page snapshot
+  :steps                 list the recorded steps
+  :undo                  drop the last recorded step
+  :quit                  save the test and exit (also Ctrl-D)
+  :abort                 exit without saving
+  :help                  show this help";
+
+/// Interactive recording session. Steps are kept locally and saved to the test
+/// when the session ends.
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    backend: Option<String>,
+) -> anyhow::Result<()> {
+    let mut test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    let project_path = api::fetch_repository(client, url, repo).await.ok().map(|r| r.path);
+    let backend = find_backend(client, url, backend).await?;
+
+    let original = test.steps.clone();
+    let mut capture = RecordCapture::default();
+
+    println!("Recording {} ({} existing steps)", test.name, original.len());
+    if let Some(backend) = &backend {
+        println!("AI backend: {} ({})", backend.label, backend.model);
+    }
+    println!("{}", HELP);
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("record> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        let line = line?;
+        let line = line.trim();
+
+        match line {
+            "" => continue,
+            ":quit" | ":q" | ":exit" => break,
+            ":abort" => {
+                println!("Recording aborted, no steps saved");
+                return Ok(());
+            }
+            ":help" => println!("{}", HELP),
+            ":steps" => {
+                for (i, step) in test.steps.iter().enumerate() {
+                    println!("  {}) {}", i + 1, step);
+                }
+            }
+            ":undo" => match test.steps.pop() {
+                Some(step) => println!("  Removed: {}", step),
+                None => println!("  No steps to remove"),
+            },
+            ":snapshot" => match api::record_capture(client, url).await {
+                Ok(c) => {
+                    println!("{}", c.snapshot);
+                    capture = c;
+                }
+                Err(e) => eprintln!("  Error: {}", e),
+            },
+            _ if line.starts_with(':') => eprintln!("  Unknown command {}, see :help", line),
+            _ => {
+                let words = match shell_words::split(line) {
+                    Ok(words) => words,
+                    Err(e) => {
+                        eprintln!("  Error: {}", e);
+                        continue;
+                    }
+                };
+                // Nothing left, as in a `# comment`
+                let Some(cmd) = words.first() else {
+                    continue;
+                };
+
+                let result = if cmd == "ai" {
+                    let Some(backend) = &backend else {
+                        eprintln!("  Error: no AI backend available");
+                        continue;
+                    };
+                    let instruction = words[1..].join(" ");
+                    record_ai(client, url, &backend.id, &instruction, &mut capture, project_path.as_deref())
+                        .await
+                } else if cmd == "prompt" || cmd == "assert" {
+                    TestStep::from_args(&words)
+                } else {
+                    record_command(client, url, &words).await
+                };
+
+                match result {
+                    Ok(step) => {
+                        println!("  + {}) {}", test.steps.len() + 1, step);
+                        test.steps.push(step);
+                        // Keep the page state fresh for the next AI step
+                        if let Ok(c) = api::record_capture(client, url).await {
+                            capture = c;
+                        }
+                    }
+                    Err(e) => eprintln!("  Error: {}", e),
+                }
+            }
+        }
+    }
+
+    if test.steps == original {
+        println!("No changes to save");
+        return Ok(());
+    }
+    api::save_test_steps(client, url, repo, &test.id, &test.steps).await?;
+    println!("✓ Saved {} steps to {}", test.steps.len(), test.id);
+    Ok(())
+}
+
+/// The AI backend with the given id, or the first available one.
+pub async fn find_backend(
+    client: &reqwest::Client,
+    url: &str,
+    id: Option<String>,
+) -> anyhow::Result<Option<AIBackend>> {
+    let backends = api::record_backends(client, url).await.unwrap_or_default();
+    match id {
+        Some(id) if !backends.iter().any(|b| b.id == id) => {
+            anyhow::bail!("Unknown AI backend: {}", id)
+        }
+        Some(id) => Ok(backends.into_iter().find(|b| b.id == id)),
+        None => Ok(backends.into_iter().next()),
+    }
+}
+
+/// Runs a command in the recording browser; only successful commands become steps.
+pub async fn record_command(
+    client: &reqwest::Client,
+    url: &str,
+    words: &[String],
+) -> anyhow::Result<TestStep> {
+    let step = TestStep::from_args(words)?;
+    let Some((command, args)) = words.split_first() else {
+        anyhow::bail!("A step needs a command");
+    };
+    let resp = api::record_execute(client, url, command, args).await?;
+    print_output(&resp.output);
+    if !resp.success {
+        anyhow::bail!("{}", resp.error.unwrap_or_else(|| "command failed".to_string()));
+    }
+    Ok(step)
+}
+
+async fn record_ai(
+    client: &reqwest::Client,
+    url: &str,
+    backend: &str,
+    instruction: &str,
+    capture: &mut RecordCapture,
+    project_path: Option<&str>,
+) -> anyhow::Result<TestStep> {
+    if instruction.is_empty() {
+        anyhow::bail!("Usage: ai <instruction>");
+    }
+    if capture.snapshot.is_empty() && capture.screenshot.is_empty() {
+        *capture = api::record_capture(client, url).await?;
+    }
+
+    let recommendation =
+        api::record_ai(client, url, backend, instruction, capture, project_path).await?;
+    if !recommendation.explanation.is_empty() {
+        println!("  AI: {}", recommendation.explanation);
+    }
+
+    let step_name = Some(recommendation.step_description).filter(|d| !d.is_empty());
+    if recommendation.kind == "prompt" {
+        // The agent already performed the action; keep it as a prompt step so runs repeat it
+        let instruction = recommendation
+            .instruction
+            .filter(|i| !i.is_empty())
+            .unwrap_or_else(|| instruction.to_string());
+        return Ok(TestStep {
+            id: None,
+            name: step_name,
+            timeout: None,
+            action: StepAction::Prompt { instruction },
+        });
+    }
+
+    if recommendation.command.is_empty() {
+        anyhow::bail!("The AI did not suggest a command");
+    }
+    let mut words = vec![recommendation.command];
+    words.extend(recommendation.args);
+    println!("  > {}", shell_words::join(&words));
+    let mut step = record_command(client, url, &words).await?;
+    step.name = step_name;
+    Ok(step)
+}
+
+fn print_output(output: &str) {
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        println!("  {}", line);
+    }
+}
@@ -1,22 +1,577 @@
+use crate::agent::{self, Agent, AgentConfig};
+use crate::agent_browser::Browser;
+use crate::api;
+use crate::http::Dispatch;
+use crate::runner::{self, CommandFailed, Hook, Output, RunConfig, TimedOut};
+use crate::types::{ExecutionLog, ExecutionResult, StepLog, TestAttempt, TestStep};
+use crate::variables::{self, DataRow, Variables};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct RunOutcome {
+    pub execution_id: Option<String>,
+    pub status: Option<String>,
+    /// One per data row for local runs, otherwise one for the whole test
+    pub cases: Vec<TestCase>,
+}
+
+pub struct TestCase {
+    pub name: String,
+    pub duration: Duration,
+    /// Failure message, with secrets masked
+    pub failure: Option<String>,
+}
+
+/// Run configs of the test and its group. Group setup hooks run before the
+/// test's and group teardown hooks after them.
+#[derive(Default)]
+struct Lifecycle {
+    group: RunConfig,
+    test: RunConfig,
+    /// Agent for prompt steps, from the flags, the environment or the configs
+    agent: Option<Agent>,
+    browser: Browser,
+}
+
+impl Lifecycle {
+    fn before_all(&self) -> Vec<&Hook> {
+        self.group.before_all.iter().chain(&self.test.before_all).collect()
+    }
+
+    fn before_each(&self) -> Vec<&Hook> {
+        self.group.before_each.iter().chain(&self.test.before_each).collect()
+    }
+
+    fn after_each(&self) -> Vec<&Hook> {
+        self.test.after_each.iter().chain(&self.group.after_each).collect()
+    }
+
+    fn after_all(&self) -> Vec<&Hook> {
+        self.test.after_all.iter().chain(&self.group.after_all).collect()
+    }
+
+    fn step_timeout(&self) -> Option<Duration> {
+        self.test.step_timeout.or(self.group.step_timeout).map(Duration::from_millis)
+    }
+
+    fn test_timeout(&self) -> Option<Duration> {
+        self.test.timeout.or(self.group.timeout).map(Duration::from_millis)
+    }
+
+    fn agent_config(&self) -> AgentConfig {
+        self.test.agent.clone().or(self.group.agent.clone())
+    }
+}
+
+/// What a local run records: the plain log lines sent to the server, and the
+/// structured step log kept in the run folder.
+#[derive(Default)]
+struct RunLog {
+    lines: Vec<String>,
+    steps: Vec<StepLog>,
+    /// Data row being run, 1-based
+    row: Option<usize>,
+}
+
+/// Something the local runner executes: a test step, or a shell command hook.
+enum Runnable<'a> {
+    Step(&'a TestStep),
+    Shell(&'a str),
+}
+
+impl<'a> From<&'a Hook> for Runnable<'a> {
+    fn from(hook: &'a Hook) -> Self {
+        match hook {
+            Hook::Shell { shell } => Runnable::Shell(shell),
+            Hook::Step(step) => Runnable::Step(step),
+        }
+    }
+}
+
+impl std::fmt::Display for Runnable<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Runnable::Step(step) => write!(f, "{}", step),
+            Runnable::Shell(command) => write!(f, "$ {}", command),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn execute(
     client: &reqwest::Client,
     url: &str,
     repo: &str,
     test_id: &str,
+    retries: u32,
+    local: bool,
+    junit: Option<&Path>,
+    agent: AgentConfig,
 ) -> anyhow::Result<()> {
+    let max_attempts = retries + 1;
+    // Attempts are recorded next to the execution results when the runs folder is local
+    let runs_dir = match retries {
+        0 => None,
+        _ => api::fetch_repository(client, url, repo)
+            .await
+            .ok()
+            .and_then(|r| api::runs_dir(&r, test_id)),
+    };
+
+    let mut first_execution_id = None;
+    let mut attempt = 0;
+    let outcome = loop {
+        attempt += 1;
+        if max_attempts > 1 {
+            println!("▶ Attempt {}/{}", attempt, max_attempts);
+        }
+
+        let outcome = if local {
+            run_local(client, url, repo, test_id, agent.clone()).await?
+        } else {
+            run_once(client, url, repo, test_id, |line| println!("  {}", line)).await?
+        };
+        let status = outcome.status.as_deref().unwrap_or("unknown");
+        let execution_id = outcome.execution_id.clone().unwrap_or_default();
+        let first = first_execution_id.get_or_insert_with(|| execution_id.clone());
+
+        if let Some(dir) = runs_dir.as_ref().map(|d| d.join(&execution_id)).filter(|d| d.is_dir()) {
+            let record = TestAttempt {
+                attempt,
+                max_attempts,
+                first_execution_id: first.clone(),
+            };
+            std::fs::write(dir.join("attempt.json"), serde_json::to_string_pretty(&record)?)?;
+        }
+
+        if status == "passed" {
+            println!("✓ Test passed ({})", execution_id);
+            if attempt > 1 {
+                println!("⚠ Passed on attempt {} of {}; the test is likely flaky", attempt, max_attempts);
+            }
+            break outcome;
+        }
+        eprintln!("✗ Test {} ({})", status, execution_id);
+        if attempt == max_attempts {
+            break outcome;
+        }
+    };
+
+    // The report describes the last attempt
+    if let Some(path) = junit {
+        write_junit(path, test_id, &outcome.cases)?;
+        println!("✓ JUnit report written to {}", path.display());
+    }
+
+    if outcome.status.as_deref() != Some("passed") {
+        anyhow::bail!("Test {} failed after {} attempts", test_id, max_attempts);
+    }
+    Ok(())
+}
+
+/// Starts a run on the server and passes each output line to `on_line` until
+/// the server reports completion.
+pub async fn run_once(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    mut on_line: impl FnMut(&str),
+) -> anyhow::Result<RunOutcome> {
+    let started = Instant::now();
     let encoded_repo = urlencoding::encode(repo);
     let request_url = format!("{}/api/tests/{}/run?repo={}", url, test_id, encoded_repo);
-    
-    let resp = client.post(request_url).send().await?;
+
+    let mut resp = client.post(request_url).dispatch().await?;
 
     if !resp.status().is_success() {
-        eprintln!("Error running test: {}", resp.status());
-        if let Ok(text) = resp.text().await {
-            eprintln!("{}", text);
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Error running test: {} {}", status, text.trim());
+    }
+
+    let mut outcome = RunOutcome {
+        execution_id: resp
+            .headers()
+            .get("x-execution-id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from),
+        status: None,
+        cases: Vec::new(),
+    };
+
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            handle_line(&String::from_utf8_lossy(&line), &mut outcome, &mut on_line);
+        }
+    }
+    if !buffer.is_empty() {
+        handle_line(&String::from_utf8_lossy(&buffer), &mut outcome, &mut on_line);
+    }
+
+    let status = outcome.status.as_deref().unwrap_or("unknown");
+    outcome.cases.push(TestCase {
+        name: test_id.to_string(),
+        duration: started.elapsed(),
+        failure: (status != "passed").then(|| format!("Test {}", status)),
+    });
+    Ok(outcome)
+}
+
+/// Runs the steps on this machine with agent-browser and reports the result
+/// through the finish endpoint. With a data table the steps run once per row;
+/// each row stops at its first failing step. Teardown hooks always run.
+pub async fn run_local(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    agent: AgentConfig,
+) -> anyhow::Result<RunOutcome> {
+    let test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    let repository = api::fetch_repository(client, url, repo).await?;
+    let secrets = variables::read_secrets(&repository)?;
+    // The data table and run configs live in the test folder, which only the index knows
+    let entry = api::fetch_test(client, url, repo, test_id).await?;
+    let (rows, mut lifecycle) = match api::test_dir(&repository, &entry) {
+        Ok(dir) => (
+            variables::read_data(&dir)?,
+            Lifecycle {
+                group: runner::read_run_config(dir.parent().unwrap_or(&dir))?,
+                test: runner::read_run_config(&dir)?,
+                ..Default::default()
+            },
+        ),
+        Err(e) => {
+            eprintln!("⚠ Running without a data table or run configs: {}", e);
+            (Vec::new(), Lifecycle::default())
+        }
+    };
+    let agent_config = agent.or(AgentConfig::from_env()).or(lifecycle.agent_config());
+    lifecycle.agent = agent::resolve(client, url, agent_config).await?;
+    if let Some(agent) = &lifecycle.agent {
+        println!("  Prompt steps run with {}", agent);
+    }
+    lifecycle.browser = Browser::new(test.browser.clone().unwrap_or_default());
+    if let Some(session) = lifecycle.browser.session() {
+        println!("  Browser session: {}", session);
+    }
+    lifecycle.browser.prepare().await?;
+
+    let started_at = chrono::Utc::now();
+    let execution_id = format!("exec-{}", started_at.timestamp_millis());
+    let mut log = RunLog::default();
+    let mut cases = Vec::new();
+    let vars = Variables::new(DataRow::new(), secrets.clone());
+
+    let setup_started = Instant::now();
+    let hooks = lifecycle.before_all();
+    let setup_failure = run_sequence(&labelled("before_all", &hooks), &vars, &lifecycle, None, true, &mut log).await;
+
+    match setup_failure {
+        Some(failure) => cases.push(TestCase {
+            name: test.name.clone(),
+            duration: setup_started.elapsed(),
+            failure: Some(failure),
+        }),
+        None if rows.is_empty() => {
+            cases.push(run_case(&test.name, &test.steps, &vars, &lifecycle, &mut log).await);
+        }
+        None => {
+            for (i, row) in rows.into_iter().enumerate() {
+                let header = format!("▶ Row {}: {}", i + 1, format_row(&row));
+                println!("  {}", header);
+                log.lines.push(header);
+
+                let vars = Variables::new(row, secrets.clone());
+                let name = format!("{} [row {}]", test.name, i + 1);
+                log.row = Some(i + 1);
+                cases.push(run_case(&name, &test.steps, &vars, &lifecycle, &mut log).await);
+            }
+        }
+    }
+
+    log.row = None;
+    let teardown_started = Instant::now();
+    let hooks = lifecycle.after_all();
+    if let Some(failure) = run_sequence(&labelled("after_all", &hooks), &vars, &lifecycle, None, false, &mut log).await {
+        cases.push(TestCase {
+            name: format!("{} [after_all]", test.name),
+            duration: teardown_started.elapsed(),
+            failure: Some(failure),
+        });
+    }
+
+    let status = if cases.iter().all(|c| c.failure.is_none()) { "passed" } else { "failed" };
+    let completed_at = chrono::Utc::now().to_rfc3339();
+
+    let step_log = ExecutionLog {
+        execution_id: execution_id.clone(),
+        test_id: test_id.to_string(),
+        status: status.to_string(),
+        started_at: started_at.to_rfc3339(),
+        completed_at: completed_at.clone(),
+        steps: log.steps,
+    };
+    let result = ExecutionResult {
+        execution_id: execution_id.clone(),
+        status: status.to_string(),
+        logs: log.lines,
+        screenshots: Vec::new(),
+        started_at: started_at.to_rfc3339(),
+        completed_at,
+    };
+    // Reported first: the result matters more than the step log kept beside it
+    api::finish_execution(client, url, repo, test_id, &result).await?;
+
+    // The runs folder of a repository on another machine can't be written from here
+    match api::execution_log_path(&repository, test_id, &execution_id) {
+        Some(log_path) => {
+            if let Err(e) = write_step_log(&log_path, &step_log) {
+                eprintln!("⚠ Could not keep the step log in {}: {}", log_path.display(), e);
+            }
+        }
+        None => eprintln!(
+            "⚠ Not keeping the step log: {} is on another machine; set local_path in the profile to keep it",
+            repo
+        ),
+    }
+
+    Ok(RunOutcome {
+        execution_id: Some(execution_id),
+        status: Some(status.to_string()),
+        cases,
+    })
+}
+
+/// Runs the steps with one set of variables, between the `before_each` and
+/// `after_each` hooks. The test timeout covers the setup hooks and the steps.
+async fn run_case(
+    name: &str,
+    steps: &[TestStep],
+    vars: &Variables,
+    lifecycle: &Lifecycle,
+    log: &mut RunLog,
+) -> TestCase {
+    let started = Instant::now();
+    let deadline = lifecycle.test_timeout().map(|timeout| started + timeout);
+
+    let hooks = lifecycle.before_each();
+    let mut failure = run_sequence(&labelled("before_each", &hooks), vars, lifecycle, deadline, true, log).await;
+    if failure.is_none() {
+        let steps: Vec<_> = steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| (format!("Step {}", i + 1), Runnable::Step(step)))
+            .collect();
+        failure = run_sequence(&steps, vars, lifecycle, deadline, true, log).await;
+    }
+
+    let hooks = lifecycle.after_each();
+    let teardown = run_sequence(&labelled("after_each", &hooks), vars, lifecycle, None, false, log).await;
+
+    TestCase {
+        name: name.to_string(),
+        duration: started.elapsed(),
+        failure: failure.or(teardown),
+    }
+}
+
+fn labelled<'a>(phase: &str, hooks: &[&'a Hook]) -> Vec<(String, Runnable<'a>)> {
+    hooks
+        .iter()
+        .enumerate()
+        .map(|(i, hook)| (format!("{} {}", phase, i + 1), Runnable::from(*hook)))
+        .collect()
+}
+
+/// Runs steps or hooks in order and returns the first failure. Setup stops at
+/// the first failure; teardown runs everything. Output and errors are masked
+/// before they are printed or logged.
+async fn run_sequence(
+    items: &[(String, Runnable<'_>)],
+    vars: &Variables,
+    lifecycle: &Lifecycle,
+    deadline: Option<Instant>,
+    stop_on_failure: bool,
+    log: &mut RunLog,
+) -> Option<String> {
+    let mut first_failure = None;
+
+    for (label, item) in items {
+        // Shown as written, with its placeholders
+        let header = format!("▶ {}: {}", label, item);
+        println!("  {}", header);
+        log.lines.push(header);
+
+        // The step timeout, shortened to what is left of the test timeout
+        let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        let timeout = match (lifecycle.step_timeout(), remaining) {
+            (Some(step), Some(remaining)) => Some(step.min(remaining)),
+            (step, remaining) => step.or(remaining),
+        };
+        let test_limited = remaining.is_some() && timeout == remaining;
+
+        let started = Instant::now();
+        let result = match timeout {
+            Some(timeout) if timeout.is_zero() => Err(anyhow::Error::new(TimedOut(timeout))),
+            _ => runner::with_timeout(timeout, run_item(item, vars, lifecycle)).await,
+        };
+        let duration = started.elapsed();
+
+        let (output, error, timed_out) = match result {
+            Ok(output) => (output, None, false),
+            Err(e) => {
+                let timed_out = e.is::<TimedOut>();
+                let message = match e.downcast_ref::<TimedOut>() {
+                    Some(_) if test_limited => format!(
+                        "{} timed out: the test ran past its {}ms timeout",
+                        label,
+                        lifecycle.test_timeout().unwrap_or_default().as_millis()
+                    ),
+                    Some(timed_out) => format!("{} {}", label, timed_out),
+                    None => vars.mask(&format!("{} failed: {}", label, e)),
+                };
+                let output = match e.downcast::<CommandFailed>() {
+                    Ok(failed) => failed.output,
+                    Err(_) => Output::default(),
+                };
+                (output, Some(message), timed_out)
+            }
+        };
+
+        let stdout = vars.mask(&output.stdout);
+        let stderr = vars.mask(&output.stderr);
+        let printed = stdout
+            .lines()
+            .map(String::from)
+            .chain(stderr.lines().map(|line| format!("stderr: {}", line)));
+        for line in printed.filter(|l| !l.trim().is_empty()) {
+            println!("    {}", line);
+            log.lines.push(line);
+        }
+        if let Some(message) = &error {
+            for line in format!("✗ {}", message).lines() {
+                println!("    {}", line);
+            }
+            log.lines.push(format!("✗ {}", message));
+        }
+
+        log.steps.push(StepLog {
+            label: label.clone(),
+            step: item.to_string(),
+            row: log.row,
+            status: match (&error, timed_out) {
+                (None, _) => "passed",
+                (Some(_), true) => "timedOut",
+                (Some(_), false) => "failed",
+            }
+            .to_string(),
+            duration: duration.as_millis() as u64,
+            stdout,
+            stderr,
+            exit_code: output.exit_code,
+            error: error.clone(),
+        });
+
+        if let Some(message) = error {
+            first_failure.get_or_insert(message);
+            if stop_on_failure {
+                break;
+            }
+        }
+    }
+
+    first_failure
+}
+
+async fn run_item(item: &Runnable<'_>, vars: &Variables, lifecycle: &Lifecycle) -> anyhow::Result<Output> {
+    match item {
+        Runnable::Step(step) => {
+            runner::run_step(&vars.interpolate_step(step)?, &lifecycle.browser, lifecycle.agent.as_ref()).await
+        }
+        Runnable::Shell(command) => runner::run_shell(&vars.interpolate(command)?).await,
+    }
+}
+
+fn write_step_log(path: &Path, log: &ExecutionLog) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(log)?)?;
+    Ok(())
+}
+
+/// The variables of a row, without their values, which may be secrets.
+fn format_row(row: &DataRow) -> String {
+    row.keys().map(String::as_str).collect::<Vec<_>>().join(", ")
+}
+
+fn write_junit(path: &Path, suite: &str, cases: &[TestCase]) -> anyhow::Result<()> {
+    let failures = cases.iter().filter(|c| c.failure.is_some()).count();
+    let total: f64 = cases.iter().map(|c| c.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\" timestamp=\"{}\">\n",
+        xml_escape(suite),
+        cases.len(),
+        failures,
+        total,
+        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S"),
+    ));
+    for case in cases {
+        xml.push_str(&format!(
+            "  <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            xml_escape(suite),
+            xml_escape(&case.name),
+            case.duration.as_secs_f64()
+        ));
+        match &case.failure {
+            Some(message) => xml.push_str(&format!(
+                ">\n    <failure message=\"{}\">{}</failure>\n  </testcase>\n",
+                xml_escape(message.lines().next().unwrap_or_default()),
+                xml_escape(message)
+            )),
+            None => xml.push_str("/>\n"),
         }
-        return Ok(());
     }
+    xml.push_str("</testsuite>\n");
 
-    println!("✓ Test started successfully");
+    std::fs::write(path, xml)?;
     Ok(())
 }
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The run stream is the runner's output framed by `exec_start` and
+/// `exec_complete` JSON events.
+fn handle_line(line: &str, outcome: &mut RunOutcome, on_line: &mut impl FnMut(&str)) {
+    let line = line.trim_end();
+    if let Ok(event) = serde_json::from_str::<serde_json::Value>(line) {
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("exec_start") => {
+                if let Some(id) = event.get("executionId").and_then(|v| v.as_str()) {
+                    outcome.execution_id = Some(id.to_string());
+                }
+                return;
+            }
+            Some("exec_complete") => {
+                outcome.status = event.get("status").and_then(|v| v.as_str()).map(String::from);
+                return;
+            }
+            _ => {}
+        }
+    }
+    if !line.is_empty() {
+        on_line(line);
+    }
+}
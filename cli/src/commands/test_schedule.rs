@@ -0,0 +1,107 @@
+use crate::api;
+use crate::schedule::{self, Schedule, ScheduleStore, TargetKind};
+
+/// Schedules a test, or every test of a group, to run every `every`.
+pub async fn add(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    target: &str,
+    every: &str,
+    on_failure: Option<String>,
+    webhook: Option<String>,
+) -> anyhow::Result<()> {
+    schedule::parse_interval(every)?;
+    let tests = api::fetch_tests(client, url, repo).await?;
+    let kind = if tests.iter().any(|t| t.id == target) {
+        TargetKind::Test
+    } else if tests.iter().any(|t| t.group.as_deref() == Some(target)) {
+        TargetKind::Group
+    } else {
+        anyhow::bail!("{} is neither a test id nor a group with tests in {}", target, repo);
+    };
+
+    let path = schedule::schedule_path()?;
+    let mut store = ScheduleStore::load(&path)?;
+    let schedule = Schedule {
+        id: store.next_id(),
+        url: url.to_string(),
+        repo: repo.to_string(),
+        target: target.to_string(),
+        kind,
+        every: every.to_string(),
+        on_failure,
+        webhook,
+        last_run: None,
+        last_status: None,
+        last_error: None,
+    };
+    println!(
+        "✓ Scheduled {} {} every {} ({})",
+        kind_label(kind),
+        target,
+        every,
+        schedule.id
+    );
+    store.schedules.push(schedule);
+    store.save(&path)?;
+    println!("  Runs start when `agelum daemon` is running");
+    Ok(())
+}
+
+pub fn list() -> anyhow::Result<()> {
+    let store = ScheduleStore::load(&schedule::schedule_path()?)?;
+    if store.schedules.is_empty() {
+        println!("No scheduled tests");
+        return Ok(());
+    }
+
+    println!(
+        "{:<5} {:<14} {:<28} {:<8} {:<20} {:<8} {:<20}",
+        "ID", "REPO", "TARGET", "EVERY", "LAST RUN", "STATUS", "NEXT RUN"
+    );
+    for s in &store.schedules {
+        let last_run = s
+            .last_run
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let next_run = match s.next_run() {
+            Some(next) => next.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => "on daemon start".to_string(),
+        };
+        println!(
+            "{:<5} {:<14} {:<28} {:<8} {:<20} {:<8} {:<20}",
+            s.id,
+            s.repo,
+            format!("{} {}", kind_label(s.kind), s.target),
+            s.every,
+            last_run,
+            s.last_status.as_deref().unwrap_or("-"),
+            next_run
+        );
+        if let Some(error) = &s.last_error {
+            println!("      {}", error.lines().next().unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+pub fn remove(id: &str) -> anyhow::Result<()> {
+    let path = schedule::schedule_path()?;
+    let mut store = ScheduleStore::load(&path)?;
+    let before = store.schedules.len();
+    store.schedules.retain(|s| s.id != id);
+    if store.schedules.len() == before {
+        anyhow::bail!("Schedule not found: {}", id);
+    }
+    store.save(&path)?;
+    println!("✓ Removed schedule {}", id);
+    Ok(())
+}
+
+fn kind_label(kind: TargetKind) -> &'static str {
+    match kind {
+        TargetKind::Test => "test",
+        TargetKind::Group => "group",
+    }
+}
@@ -0,0 +1,210 @@
+use crate::api;
+use crate::types::{TestAttempt, TestExecution};
+use std::path::Path;
+
+/// Share of consecutive runs that flip between pass and fail above which a test
+/// is reported as flaky.
+const FLAKY_THRESHOLD: f64 = 0.2;
+
+struct TestStats {
+    id: String,
+    runs: usize,
+    pass_rate: f64,
+    mean_ms: f64,
+    p95_ms: f64,
+    last_failure: Option<String>,
+    flakiness: f64,
+    retry_passes: usize,
+}
+
+impl TestStats {
+    /// Alternating between pass and fail, or passing only on a retry, marks a test as flaky.
+    fn is_flaky(&self) -> bool {
+        self.flakiness >= FLAKY_THRESHOLD || self.retry_passes > 0
+    }
+}
+
+pub async fn execute(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    group: Option<String>,
+    only_flaky: bool,
+) -> anyhow::Result<()> {
+    let tests = api::fetch_tests(client, url, repo).await?;
+    // Attempt records only exist when the runs folder is local
+    let repository = api::fetch_repository(client, url, repo).await.ok();
+
+    let mut all_stats = Vec::new();
+    for test in tests {
+        if group.is_some() && test.group != group {
+            continue;
+        }
+        let executions = api::fetch_executions(client, url, repo, &test.id).await?;
+        if executions.is_empty() {
+            continue;
+        }
+        let runs_dir = repository.as_ref().and_then(|r| api::runs_dir(r, &test.id));
+        all_stats.push(compute(&test.id, &executions, runs_dir.as_deref()));
+    }
+
+    let shown: Vec<_> = all_stats
+        .iter()
+        .filter(|s| !only_flaky || s.is_flaky())
+        .collect();
+    if shown.is_empty() {
+        println!("No {} with executions found", if only_flaky { "flaky tests" } else { "tests" });
+        return Ok(());
+    }
+
+    println!(
+        "{:<30} {:>5} {:>7} {:>8} {:>8} {:>6}  LAST FAILURE",
+        "TEST", "RUNS", "PASS", "MEAN", "P95", "FLAKY"
+    );
+    for stats in shown {
+        println!(
+            "{:<30} {:>5} {:>6.1}% {:>8} {:>8} {:>6.2}{} {}",
+            stats.id,
+            stats.runs,
+            stats.pass_rate * 100.0,
+            format_duration(stats.mean_ms),
+            format_duration(stats.p95_ms),
+            stats.flakiness,
+            if stats.is_flaky() { "⚠" } else { " " },
+            stats.last_failure.as_deref().unwrap_or("-"),
+        );
+        if stats.retry_passes > 0 {
+            println!("{:<30} passed on retry {} times", "", stats.retry_passes);
+        }
+    }
+
+    Ok(())
+}
+
+/// `executions` are most recent first, as returned by the API.
+fn compute(id: &str, executions: &[TestExecution], runs_dir: Option<&Path>) -> TestStats {
+    let passed: Vec<bool> = executions.iter().rev().map(|e| e.status == "passed").collect();
+    let runs = passed.len();
+    let passes = passed.iter().filter(|p| **p).count();
+
+    let flips = passed.windows(2).filter(|w| w[0] != w[1]).count();
+    let flakiness = if runs > 1 { flips as f64 / (runs - 1) as f64 } else { 0.0 };
+
+    let mut durations: Vec<f64> = executions
+        .iter()
+        .filter_map(|e| e.duration)
+        .map(|d| d as f64)
+        .collect();
+    durations.sort_by(|a, b| a.total_cmp(b));
+    let mean_ms = if durations.is_empty() {
+        0.0
+    } else {
+        durations.iter().sum::<f64>() / durations.len() as f64
+    };
+    // Nearest-rank percentile
+    let p95_ms = match durations.len() {
+        0 => 0.0,
+        n => durations[((n as f64 * 0.95).ceil() as usize).clamp(1, n) - 1],
+    };
+
+    let retry_passes = executions
+        .iter()
+        .filter(|e| e.status == "passed")
+        .filter_map(|e| read_attempt(runs_dir?, &e.id))
+        .filter(|a| a.attempt > 1)
+        .count();
+
+    TestStats {
+        id: id.to_string(),
+        runs,
+        pass_rate: passes as f64 / runs as f64,
+        mean_ms,
+        p95_ms,
+        last_failure: executions
+            .iter()
+            .find(|e| e.status != "passed")
+            .map(|e| e.started_at.clone()),
+        flakiness,
+        retry_passes,
+    }
+}
+
+fn read_attempt(runs_dir: &Path, execution_id: &str) -> Option<TestAttempt> {
+    let content = std::fs::read_to_string(runs_dir.join(execution_id).join("attempt.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn format_duration(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{:.0}ms", ms)
+    } else {
+        format!("{:.1}s", ms / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Executions from statuses and durations listed oldest first; the API
+    /// returns them most recent first.
+    fn executions(runs: &[(&str, u64)]) -> Vec<TestExecution> {
+        runs.iter()
+            .enumerate()
+            .rev()
+            .map(|(i, (status, duration))| TestExecution {
+                id: format!("exec-{}", i + 1),
+                test_id: "t1".to_string(),
+                started_at: format!("2026-01-{:02}T00:00:00Z", i + 1),
+                completed_at: None,
+                status: status.to_string(),
+                duration: Some(*duration),
+                error: None,
+                screenshots: Vec::new(),
+                logs: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flakiness_is_the_rate_of_status_flips() {
+        let runs = [("passed", 1), ("failed", 1), ("passed", 1), ("passed", 1), ("passed", 1)];
+        let stats = compute("t1", &executions(&runs), None);
+        assert_eq!(stats.runs, 5);
+        assert_eq!(stats.pass_rate, 0.8);
+        assert_eq!(stats.flakiness, 0.5);
+        assert!(stats.is_flaky());
+        assert_eq!(stats.last_failure.as_deref(), Some("2026-01-02T00:00:00Z"));
+
+        let mut runs = vec![("failed", 1)];
+        runs.extend([("passed", 1); 9]);
+        let stable = compute("t1", &executions(&runs), None);
+        assert_eq!(stable.flakiness, 1.0 / 9.0);
+        assert!(!stable.is_flaky());
+        assert_eq!(compute("t1", &executions(&[("failed", 1)]), None).flakiness, 0.0);
+    }
+
+    #[test]
+    fn p95_is_the_nearest_rank() {
+        let runs: Vec<(&str, u64)> = (1..=20).map(|i| ("passed", i * 100)).collect();
+        let stats = compute("t1", &executions(&runs), None);
+        assert_eq!(stats.p95_ms, 1900.0);
+        assert_eq!(stats.mean_ms, 1050.0);
+
+        let stats = compute("t1", &executions(&[("passed", 300), ("passed", 100)]), None);
+        assert_eq!(stats.p95_ms, 300.0);
+    }
+
+    #[test]
+    fn passes_on_a_retry_count_as_flaky() {
+        let runs_dir = tempfile::tempdir().unwrap();
+        let attempt_dir = runs_dir.path().join("exec-2");
+        std::fs::create_dir_all(&attempt_dir).unwrap();
+        let attempt = r#"{"attempt": 2, "maxAttempts": 3, "firstExecutionId": "exec-1"}"#;
+        std::fs::write(attempt_dir.join("attempt.json"), attempt).unwrap();
+
+        let runs = executions(&[("failed", 1), ("passed", 1)]);
+        assert_eq!(compute("t1", &runs, Some(runs_dir.path())).retry_passes, 1);
+        assert_eq!(compute("t1", &runs, None).retry_passes, 0);
+    }
+}
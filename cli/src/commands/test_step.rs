@@ -0,0 +1,164 @@
+use crate::api;
+use crate::interactive::{confirm, open_editor};
+use crate::types::{Test, TestStep};
+
+// Step positions are 1-based, matching the numbering printed by `test-steps`.
+
+pub async fn insert(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    at: usize,
+    args: Vec<String>,
+) -> anyhow::Result<()> {
+    let step = TestStep::from_args(&args)?;
+    let mut test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    if at == 0 || at > test.steps.len() + 1 {
+        anyhow::bail!("Position {} is out of range (1-{})", at, test.steps.len() + 1);
+    }
+
+    let message = format!("✓ Inserted step {}: {}", at, step);
+    test.steps.insert(at - 1, step);
+    save(client, url, repo, &test).await?;
+    println!("{}", message);
+    Ok(())
+}
+
+pub async fn remove(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    index: usize,
+) -> anyhow::Result<()> {
+    let mut test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    check_index(&test, index)?;
+
+    let step = test.steps.remove(index - 1);
+    save(client, url, repo, &test).await?;
+    println!("✓ Removed step {}: {}", index, step);
+    Ok(())
+}
+
+pub async fn move_step(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    from: usize,
+    to: usize,
+) -> anyhow::Result<()> {
+    let mut test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    check_index(&test, from)?;
+    check_index(&test, to)?;
+
+    let step = test.steps.remove(from - 1);
+    let message = format!("✓ Moved step {} to {}: {}", from, to, step);
+    test.steps.insert(to - 1, step);
+    save(client, url, repo, &test).await?;
+    println!("{}", message);
+    Ok(())
+}
+
+pub async fn set(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    index: usize,
+    args: Vec<String>,
+) -> anyhow::Result<()> {
+    let step = TestStep::from_args(&args)?;
+    let mut test = api::fetch_test_definition(client, url, repo, test_id).await?;
+    check_index(&test, index)?;
+
+    let message = format!("✓ Replaced step {}: {}", index, step);
+    test.steps[index - 1] = step;
+    save(client, url, repo, &test).await?;
+    println!("{}", message);
+    Ok(())
+}
+
+/// Opens the step list in `$VISUAL`/`$EDITOR` and saves it once it parses and validates.
+pub async fn edit(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    test_id: &str,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut test = api::fetch_test_definition(client, url, repo, test_id).await?;
+
+    let extension = if json { "json" } else { "yaml" };
+    // Private to the user, and removed with the directory
+    let dir = tempfile::Builder::new().prefix("agelum-steps").tempdir()?;
+    let path = dir.path().join(format!("{}.{}", test_id, extension));
+    let original = if json {
+        serde_json::to_string_pretty(&test.steps)?
+    } else {
+        serde_yaml::to_string(&test.steps)?
+    };
+    std::fs::write(&path, &original)?;
+
+    let result = loop {
+        open_editor(&path)?;
+        let content = std::fs::read_to_string(&path)?;
+        if content == original {
+            break None;
+        }
+
+        match parse_steps(&content, json) {
+            Ok(steps) => break Some(steps),
+            Err(e) => {
+                eprintln!("Invalid step list: {}", e);
+                if !confirm("Re-open the editor?", true)? {
+                    break None;
+                }
+            }
+        }
+    };
+    drop(dir);
+
+    match result {
+        Some(steps) => {
+            test.steps = steps;
+            save(client, url, repo, &test).await?;
+            println!("✓ Saved {} steps", test.steps.len());
+            Ok(())
+        }
+        None => {
+            println!("No changes saved");
+            Ok(())
+        }
+    }
+}
+
+fn check_index(test: &Test, index: usize) -> anyhow::Result<()> {
+    if index == 0 || index > test.steps.len() {
+        anyhow::bail!(
+            "Step {} does not exist (test {} has {} steps)",
+            index,
+            test.id,
+            test.steps.len()
+        );
+    }
+    Ok(())
+}
+
+async fn save(client: &reqwest::Client, url: &str, repo: &str, test: &Test) -> anyhow::Result<()> {
+    api::save_test_steps(client, url, repo, &test.id, &test.steps).await
+}
+
+fn parse_steps(content: &str, json: bool) -> anyhow::Result<Vec<TestStep>> {
+    let steps: Vec<TestStep> = if json {
+        serde_json::from_str(content)?
+    } else {
+        serde_yaml::from_str(content)?
+    };
+    for (i, step) in steps.iter().enumerate() {
+        step.validate()
+            .map_err(|e| anyhow::anyhow!("step {}: {}", i + 1, e))?;
+    }
+    Ok(steps)
+}
@@ -1,4 +1,5 @@
-use crate::types::TestStepResponse;
+use crate::http::Dispatch;
+use crate::types::TestStep;
 
 pub async fn execute(
     client: &reqwest::Client,
@@ -9,7 +10,7 @@ pub async fn execute(
     let encoded_repo = urlencoding::encode(repo);
     let request_url = format!("{}/api/tests/{}/steps?repo={}", url, test_id, encoded_repo);
     
-    let resp = client.get(request_url).send().await?;
+    let resp = client.get(request_url).dispatch().await?;
 
     if !resp.status().is_success() {
         eprintln!("Error fetching test steps: {}", resp.status());
@@ -19,14 +20,14 @@ pub async fn execute(
         return Ok(());
     }
 
-    let steps_resp: TestStepResponse = resp.json().await?;
+    let steps: Vec<TestStep> = resp.json().await?;
     
-    if steps_resp.steps.is_empty() {
+    if steps.is_empty() {
         println!("No steps found for test {}", test_id);
     } else {
         println!("Test Steps for {}:", test_id);
-        for step in steps_resp.steps {
-            println!("  {}) {} {}", step.order, step.command, step.args.join(" "));
+        for (i, step) in steps.iter().enumerate() {
+            println!("  {}) {}", i + 1, step);
         }
     }
 
@@ -0,0 +1,381 @@
+use crate::api;
+use crate::config;
+use crate::agent_browser::BrowserOptions;
+use crate::types::Test;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SyncState {
+    InSync,
+    LocalNew,
+    RemoteNew,
+    LocalModified,
+    RemoteModified,
+    LocalDeleted,
+    RemoteDeleted,
+    Conflict,
+}
+
+impl std::fmt::Display for SyncState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // `pad` so the state lines up in `status` output
+        f.pad(match self {
+            SyncState::InSync => "in sync",
+            SyncState::LocalNew => "new locally",
+            SyncState::RemoteNew => "new on server",
+            SyncState::LocalModified => "modified locally",
+            SyncState::RemoteModified => "modified on server",
+            SyncState::LocalDeleted => "deleted locally",
+            SyncState::RemoteDeleted => "deleted on server",
+            SyncState::Conflict => "conflict",
+        })
+    }
+}
+
+struct Entry {
+    id: String,
+    local: Option<Test>,
+    remote: Option<Test>,
+    state: SyncState,
+}
+
+impl Entry {
+    fn local_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.yaml", self.id))
+    }
+}
+
+pub async fn status(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    dir: &Path,
+) -> anyhow::Result<()> {
+    let entries = compare(client, url, repo, dir).await?;
+    let changed: Vec<_> = entries.iter().filter(|e| e.state != SyncState::InSync).collect();
+
+    if changed.is_empty() {
+        println!("✓ {} tests in sync with {}", entries.len(), dir.display());
+        return Ok(());
+    }
+
+    println!("Tests in {} compared to {}:", dir.display(), repo);
+    for entry in changed {
+        let hint = match entry.state {
+            SyncState::LocalNew | SyncState::LocalModified | SyncState::LocalDeleted => "push",
+            SyncState::RemoteNew | SyncState::RemoteModified | SyncState::RemoteDeleted => "pull",
+            _ => "resolve with --force",
+        };
+        println!("  {:<20} {} ({})", entry.state, entry.id, hint);
+    }
+    Ok(())
+}
+
+/// Updates the local specs with the server's tests. Local-only changes are kept.
+pub async fn pull(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    dir: &Path,
+    force: bool,
+) -> anyhow::Result<()> {
+    let entries = compare(client, url, repo, dir).await?;
+    let mut state = read_state(url, repo, dir)?;
+    let mut conflicts = Vec::new();
+    std::fs::create_dir_all(dir)?;
+
+    for entry in &entries {
+        let take_remote = match entry.state {
+            SyncState::RemoteNew | SyncState::RemoteModified | SyncState::RemoteDeleted => true,
+            SyncState::Conflict if force => true,
+            SyncState::Conflict => {
+                conflicts.push(entry.id.as_str());
+                false
+            }
+            SyncState::InSync => {
+                state.insert(entry.id.clone(), hash(entry.remote.as_ref().unwrap())?);
+                false
+            }
+            _ => false,
+        };
+        if !take_remote {
+            continue;
+        }
+
+        let path = entry.local_path(dir);
+        match &entry.remote {
+            Some(remote) => {
+                std::fs::write(&path, serde_yaml::to_string(remote)?)?;
+                state.insert(entry.id.clone(), hash(remote)?);
+                println!("✓ Pulled {} ({})", entry.id, entry.state);
+            }
+            None => {
+                if path.exists() {
+                    std::fs::remove_file(&path)?;
+                }
+                state.remove(&entry.id);
+                println!("✓ Removed {} ({})", entry.id, entry.state);
+            }
+        }
+    }
+
+    state.retain(|id, _| entries.iter().any(|e| &e.id == id));
+    write_state(url, repo, dir, &state)?;
+    report_conflicts(&conflicts, "pull")
+}
+
+/// Publishes the local specs to the server. Server-only changes are kept.
+pub async fn push(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    dir: &Path,
+    force: bool,
+) -> anyhow::Result<()> {
+    if !dir.exists() {
+        anyhow::bail!("Spec directory {} does not exist. Run `agelum test pull` first", dir.display());
+    }
+
+    let entries = compare(client, url, repo, dir).await?;
+    let mut state = read_state(url, repo, dir)?;
+    let mut conflicts = Vec::new();
+
+    for entry in &entries {
+        let take_local = match entry.state {
+            SyncState::LocalNew | SyncState::LocalModified | SyncState::LocalDeleted => true,
+            SyncState::Conflict if force => true,
+            SyncState::Conflict => {
+                conflicts.push(entry.id.as_str());
+                false
+            }
+            SyncState::InSync => {
+                state.insert(entry.id.clone(), hash(entry.local.as_ref().unwrap())?);
+                false
+            }
+            _ => false,
+        };
+        if !take_local {
+            continue;
+        }
+
+        match &entry.local {
+            Some(local) => {
+                for (i, step) in local.steps.iter().enumerate() {
+                    step.validate()
+                        .map_err(|e| anyhow::anyhow!("{} step {}: {}", entry.id, i + 1, e))?;
+                }
+                api::save_test(client, url, repo, local).await?;
+                state.insert(entry.id.clone(), hash(local)?);
+                println!("✓ Pushed {} ({})", entry.id, entry.state);
+            }
+            None => {
+                api::delete_test(client, url, repo, &entry.id).await?;
+                state.remove(&entry.id);
+                println!("✓ Deleted {} on server ({})", entry.id, entry.state);
+            }
+        }
+    }
+
+    state.retain(|id, _| entries.iter().any(|e| &e.id == id));
+    write_state(url, repo, dir, &state)?;
+    report_conflicts(&conflicts, "push")
+}
+
+fn report_conflicts(conflicts: &[&str], action: &str) -> anyhow::Result<()> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "{} tests changed on both sides: {}. Re-run {} with --force to overwrite the other side",
+        conflicts.len(),
+        conflicts.join(", "),
+        action
+    )
+}
+
+async fn compare(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    dir: &Path,
+) -> anyhow::Result<Vec<Entry>> {
+    let mut local = read_specs(dir)?;
+    let mut remote = fetch_remote(client, url, repo).await?;
+    let state = read_state(url, repo, dir)?;
+
+    let ids: BTreeSet<String> = local.keys().chain(remote.keys()).cloned().collect();
+    let mut entries = Vec::new();
+    for id in ids {
+        let (local, remote) = (local.remove(&id), remote.remove(&id));
+        let local_hash = local.as_ref().map(hash).transpose()?;
+        let remote_hash = remote.as_ref().map(hash).transpose()?;
+        let state = classify(local_hash, remote_hash, state.get(&id).cloned());
+        entries.push(Entry { id, local, remote, state });
+    }
+    Ok(entries)
+}
+
+fn classify(local: Option<String>, remote: Option<String>, base: Option<String>) -> SyncState {
+    match (local, remote, base) {
+        (Some(l), Some(r), _) if l == r => SyncState::InSync,
+        (Some(_), None, None) => SyncState::LocalNew,
+        (None, Some(_), None) => SyncState::RemoteNew,
+        (Some(_), Some(r), Some(b)) if r == b => SyncState::LocalModified,
+        (Some(l), Some(_), Some(b)) if l == b => SyncState::RemoteModified,
+        (None, Some(r), Some(b)) if r == b => SyncState::LocalDeleted,
+        (Some(l), None, Some(b)) if l == b => SyncState::RemoteDeleted,
+        _ => SyncState::Conflict,
+    }
+}
+
+/// Fills the fields the server derives, so specs that omit them compare equal.
+fn normalize(mut test: Test) -> Test {
+    if test.group.is_none() {
+        test.group = Some(api::DEFAULT_TEST_GROUP.to_string());
+    }
+    if test.folder.is_none() {
+        test.folder = Some(api::test_folder_name(&test.name));
+    }
+    if test.description.as_deref().is_some_and(|d| d.is_empty()) {
+        test.description = None;
+    }
+    if test.browser.as_ref().is_some_and(|b| *b == BrowserOptions::default()) {
+        test.browser = None;
+    }
+    test
+}
+
+fn hash(test: &Test) -> anyhow::Result<String> {
+    let json = serde_json::to_vec(test)?;
+    Ok(format!("{:x}", Sha256::digest(json)))
+}
+
+fn read_specs(dir: &Path) -> anyhow::Result<BTreeMap<String, Test>> {
+    let mut specs = BTreeMap::new();
+    if !dir.exists() {
+        return Ok(specs);
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "yaml") {
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let test: Test = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        if stem != test.id {
+            anyhow::bail!("{} declares id {}; name the file {}.yaml", path.display(), test.id, test.id);
+        }
+        specs.insert(test.id.clone(), normalize(test));
+    }
+    Ok(specs)
+}
+
+async fn fetch_remote(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+) -> anyhow::Result<BTreeMap<String, Test>> {
+    let mut tests = BTreeMap::new();
+    for mut test in api::fetch_tests(client, url, repo).await? {
+        // The index holds the metadata, test.json the steps and browser settings;
+        // a test created without steps may have no test.json yet
+        if let Some(definition) = api::find_test_definition(client, url, repo, &test.id).await? {
+            test.steps = definition.steps;
+            test.browser = definition.browser;
+        }
+        tests.insert(test.id.clone(), normalize(test));
+    }
+    Ok(tests)
+}
+
+/// Hash of every test as it was on both sides after the last pull or push: the
+/// common base used to tell which side changed. Kept under the Agelum home, one
+/// file per server, repository and spec directory, so it is never committed with
+/// the specs.
+fn state_path(url: &str, repo: &str, dir: &Path) -> anyhow::Result<PathBuf> {
+    let dir = std::path::absolute(dir)?;
+    let key = format!("{}\n{}\n{}", url.trim_end_matches('/'), repo, dir.display());
+    Ok(config::agelum_home()?.join("sync").join(format!("{:x}.json", Sha256::digest(key))))
+}
+
+fn read_state(url: &str, repo: &str, dir: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let path = state_path(url, repo, dir)?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn write_state(url: &str, repo: &str, dir: &Path, state: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    let path = state_path(url, repo, dir)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some(hash: &str) -> Option<String> {
+        Some(hash.to_string())
+    }
+
+    #[test]
+    fn classifies_against_the_last_sync() {
+        assert_eq!(classify(some("a"), some("a"), None), SyncState::InSync);
+        assert_eq!(classify(some("a"), some("a"), some("old")), SyncState::InSync);
+        assert_eq!(classify(some("a"), None, None), SyncState::LocalNew);
+        assert_eq!(classify(None, some("a"), None), SyncState::RemoteNew);
+        assert_eq!(classify(some("new"), some("base"), some("base")), SyncState::LocalModified);
+        assert_eq!(classify(some("base"), some("new"), some("base")), SyncState::RemoteModified);
+        assert_eq!(classify(None, some("base"), some("base")), SyncState::LocalDeleted);
+        assert_eq!(classify(some("base"), None, some("base")), SyncState::RemoteDeleted);
+    }
+
+    #[test]
+    fn changes_on_both_sides_conflict() {
+        assert_eq!(classify(some("l"), some("r"), some("base")), SyncState::Conflict);
+        assert_eq!(classify(some("l"), some("r"), None), SyncState::Conflict);
+        assert_eq!(classify(None, some("r"), some("base")), SyncState::Conflict);
+        assert_eq!(classify(some("l"), None, some("base")), SyncState::Conflict);
+    }
+
+    #[test]
+    fn normalizes_the_fields_the_server_derives() {
+        let spec = Test {
+            id: "login".to_string(),
+            name: "Login works!".to_string(),
+            group: None,
+            description: Some(String::new()),
+            folder: None,
+            steps: Vec::new(),
+            browser: None,
+        };
+        let server = Test {
+            group: Some("EXPERIMENTAL".to_string()),
+            description: None,
+            folder: Some("Loginworks".to_string()),
+            ..spec.clone()
+        };
+
+        assert_eq!(hash(&normalize(spec.clone())).unwrap(), hash(&normalize(server.clone())).unwrap());
+        assert_eq!(normalize(server.clone()).folder, server.folder);
+
+        // An empty browser section is the same as none; settings are compared
+        let browser = BrowserOptions { headed: Some(true), ..Default::default() };
+        assert_eq!(normalize(Test { browser: Some(BrowserOptions::default()), ..spec.clone() }).browser, None);
+        assert_ne!(
+            hash(&normalize(Test { browser: Some(browser), ..spec.clone() })).unwrap(),
+            hash(&normalize(spec)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,525 @@
+use crate::api;
+use crate::commands::test_run::{self, RunOutcome};
+use crate::commands::{test_log, test_stats};
+use crate::tui::{Event, Tui};
+use crate::types::{Repository, Test, TestExecution};
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const HELP: &str = "↑/↓ select  r run  f re-run failures  s steps  o latest log  Esc summary  u refresh  q quit";
+
+/// A test with its executions, most recent first.
+struct TestRow {
+    test: Test,
+    executions: Vec<TestExecution>,
+}
+
+impl TestRow {
+    fn last(&self) -> Option<&TestExecution> {
+        self.executions.first()
+    }
+
+    fn pass_rate(&self) -> Option<f64> {
+        let passed = self.executions.iter().filter(|e| e.status == "passed").count();
+        (!self.executions.is_empty()).then(|| passed as f64 / self.executions.len() as f64)
+    }
+
+    fn group(&self) -> &str {
+        self.test.group.as_deref().unwrap_or("-")
+    }
+}
+
+/// A line of the table: a group heading or one of its tests.
+enum Entry {
+    Group(String),
+    Test(usize),
+}
+
+/// What the pane below the table shows.
+enum Detail {
+    /// Output of the run started from the dashboard, or the recent executions
+    Summary,
+    Steps(Vec<String>),
+    Log(Vec<String>),
+}
+
+/// Sent by a run while it streams.
+enum RunEvent {
+    Output(String, String),
+    Done(String, anyhow::Result<RunOutcome>),
+}
+
+struct Dashboard {
+    rows: Vec<TestRow>,
+    entries: Vec<Entry>,
+    selected: usize,
+    detail: Detail,
+    scroll: u16,
+    /// Tests waiting to run; runs go one at a time like in the desktop app
+    queue: VecDeque<String>,
+    running: Option<(String, Instant)>,
+    /// Output of the runs started from the dashboard, by test id
+    output: HashMap<String, Vec<String>>,
+    message: Option<(String, bool)>,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        Dashboard {
+            rows: Vec::new(),
+            entries: Vec::new(),
+            selected: 0,
+            detail: Detail::Summary,
+            scroll: 0,
+            queue: VecDeque::new(),
+            running: None,
+            output: HashMap::new(),
+            message: None,
+        }
+    }
+
+    /// Replaces the tests, grouping them and keeping the selection in place.
+    fn set_rows(&mut self, mut rows: Vec<TestRow>) {
+        let current = self.selected_key();
+        rows.sort_by(|a, b| a.group().cmp(b.group()).then_with(|| a.test.name.cmp(&b.test.name)));
+        self.entries.clear();
+        for (i, row) in rows.iter().enumerate() {
+            if i == 0 || rows[i - 1].group() != row.group() {
+                self.entries.push(Entry::Group(row.group().to_string()));
+            }
+            self.entries.push(Entry::Test(i));
+        }
+        self.rows = rows;
+        self.selected = current
+            .and_then(|key| self.entries.iter().position(|e| self.entry_key(e) == key))
+            .unwrap_or(0);
+    }
+
+    fn entry_key(&self, entry: &Entry) -> String {
+        match entry {
+            Entry::Group(name) => format!("group:{}", name),
+            Entry::Test(i) => self.rows[*i].test.id.clone(),
+        }
+    }
+
+    fn selected_key(&self) -> Option<String> {
+        self.entries.get(self.selected).map(|e| self.entry_key(e))
+    }
+
+    fn selected_row(&self) -> Option<&TestRow> {
+        match self.entries.get(self.selected)? {
+            Entry::Test(i) => self.rows.get(*i),
+            Entry::Group(_) => None,
+        }
+    }
+
+    fn row_mut(&mut self, test_id: &str) -> Option<&mut TestRow> {
+        self.rows.iter_mut().find(|r| r.test.id == test_id)
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.entries.len().saturating_sub(1));
+        self.detail = Detail::Summary;
+        self.scroll = 0;
+    }
+
+    fn is_busy(&self, test_id: &str) -> bool {
+        self.queue.iter().any(|id| id == test_id) || self.running.as_ref().is_some_and(|(id, _)| id == test_id)
+    }
+
+    /// Queues tests that are not already queued or running; returns how many.
+    fn enqueue(&mut self, ids: Vec<String>) -> usize {
+        let mut added = 0;
+        for id in ids {
+            if !self.is_busy(&id) {
+                self.queue.push_back(id);
+                added += 1;
+            }
+        }
+        added
+    }
+}
+
+struct Context<'a> {
+    client: &'a reqwest::Client,
+    url: &'a str,
+    repo: &'a str,
+    /// Step logs of local runs can only be read when the repository is on this machine
+    repository: Option<Repository>,
+    runs: mpsc::UnboundedSender<RunEvent>,
+}
+
+/// Dashboard of the repository's tests, mirroring the desktop Test Management view.
+pub async fn execute(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<()> {
+    // Fail before taking over the terminal when the server isn't there
+    let rows = load_rows(client, url, repo).await?;
+    let (runs, mut run_events) = mpsc::unbounded_channel();
+    let ctx = Context {
+        client,
+        url,
+        repo,
+        repository: api::fetch_repository(client, url, repo).await.ok(),
+        runs,
+    };
+
+    let mut dashboard = Dashboard::new();
+    dashboard.set_rows(rows);
+
+    let mut tui = Tui::start()?;
+    // Keeps the elapsed time of a running test current
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        start_next(&ctx, &mut dashboard);
+        tui.terminal.draw(|frame| draw(frame, repo, &dashboard))?;
+
+        tokio::select! {
+            event = tui.events.recv() => match event {
+                Some(Event::Key(key)) => {
+                    if !handle_key(&ctx, &mut dashboard, key.code, key.modifiers).await {
+                        break;
+                    }
+                }
+                Some(_) => {}
+                None => break,
+            },
+            Some(event) = run_events.recv() => handle_run_event(&ctx, &mut dashboard, event).await,
+            _ = tick.tick() => {}
+        }
+    }
+    Ok(())
+}
+
+async fn load_rows(client: &reqwest::Client, url: &str, repo: &str) -> anyhow::Result<Vec<TestRow>> {
+    let tests = api::fetch_tests(client, url, repo).await?;
+    let mut rows = Vec::new();
+    for test in tests {
+        let executions = api::fetch_executions(client, url, repo, &test.id).await.unwrap_or_default();
+        rows.push(TestRow { test, executions });
+    }
+    Ok(rows)
+}
+
+/// Handles one key press; false quits the dashboard.
+async fn handle_key(ctx: &Context<'_>, dashboard: &mut Dashboard, code: KeyCode, modifiers: KeyModifiers) -> bool {
+    let selected = dashboard.selected;
+    match code {
+        KeyCode::Char('q') => return false,
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return false,
+        KeyCode::Up | KeyCode::Char('k') => dashboard.select(selected.saturating_sub(1)),
+        KeyCode::Down | KeyCode::Char('j') => dashboard.select(selected + 1),
+        KeyCode::Char('g') | KeyCode::Home => dashboard.select(0),
+        KeyCode::Char('G') | KeyCode::End => dashboard.select(usize::MAX),
+        KeyCode::PageUp | KeyCode::PageDown => {
+            // Run output is scrolled back from its end
+            let from_end = matches!(dashboard.detail, Detail::Summary)
+                && dashboard.selected_row().is_some_and(|r| dashboard.output.contains_key(&r.test.id));
+            dashboard.scroll = if (code == KeyCode::PageUp) == from_end {
+                dashboard.scroll.saturating_add(10)
+            } else {
+                dashboard.scroll.saturating_sub(10)
+            };
+        }
+        KeyCode::Esc => {
+            dashboard.detail = Detail::Summary;
+            dashboard.scroll = 0;
+        }
+        KeyCode::Enter | KeyCode::Char('r') => {
+            // A group heading runs the whole group
+            let ids: Vec<String> = match dashboard.entries.get(selected) {
+                Some(Entry::Group(group)) => dashboard
+                    .rows
+                    .iter()
+                    .filter(|r| r.group() == group)
+                    .map(|r| r.test.id.clone())
+                    .collect(),
+                Some(Entry::Test(i)) => vec![dashboard.rows[*i].test.id.clone()],
+                None => Vec::new(),
+            };
+            let added = dashboard.enqueue(ids);
+            dashboard.message = match added {
+                0 => Some(("Already running or queued".to_string(), true)),
+                _ => None,
+            };
+        }
+        KeyCode::Char('f') => {
+            let failed: Vec<String> = dashboard
+                .rows
+                .iter()
+                .filter(|r| r.last().is_some_and(|e| e.status != "passed"))
+                .map(|r| r.test.id.clone())
+                .collect();
+            dashboard.message = match dashboard.enqueue(failed) {
+                0 => Some(("No failed tests to re-run".to_string(), false)),
+                n => Some((format!("Re-running {} failed tests", n), false)),
+            };
+        }
+        KeyCode::Char('s') => {
+            if let Some(row) = dashboard.selected_row() {
+                let lines = match api::fetch_test_definition(ctx.client, ctx.url, ctx.repo, &row.test.id).await {
+                    Ok(test) if test.steps.is_empty() => vec!["No steps".to_string()],
+                    Ok(test) => test
+                        .steps
+                        .iter()
+                        .enumerate()
+                        .map(|(i, step)| match &step.name {
+                            Some(name) => format!("{}) {}  # {}", i + 1, step, name),
+                            None => format!("{}) {}", i + 1, step),
+                        })
+                        .collect(),
+                    Err(e) => vec![format!("Could not load the steps: {}", e)],
+                };
+                dashboard.detail = Detail::Steps(lines);
+                dashboard.scroll = 0;
+            }
+        }
+        KeyCode::Char('o') => {
+            if let Some(row) = dashboard.selected_row() {
+                dashboard.detail = Detail::Log(latest_log(ctx, row));
+                dashboard.scroll = 0;
+            }
+        }
+        KeyCode::Char('u') => match load_rows(ctx.client, ctx.url, ctx.repo).await {
+            Ok(rows) => {
+                dashboard.set_rows(rows);
+                dashboard.message = None;
+            }
+            Err(e) => dashboard.message = Some((format!("Refresh failed: {}", e), true)),
+        },
+        _ => {}
+    }
+    true
+}
+
+/// The step log of the latest execution when it was a local run, otherwise
+/// the output the server recorded.
+fn latest_log(ctx: &Context<'_>, row: &TestRow) -> Vec<String> {
+    let Some(execution) = row.last() else {
+        return vec!["No executions yet".to_string()];
+    };
+    let mut lines = vec![format!(
+        "Execution {}: {} (started {})",
+        execution.id, execution.status, execution.started_at
+    )];
+    let step_log = ctx
+        .repository
+        .as_ref()
+        .filter(|r| api::execution_log_path(r, &row.test.id, &execution.id).is_some_and(|p| p.exists()))
+        .map(|r| api::read_execution_log(r, &row.test.id, &execution.id));
+    match step_log {
+        Some(Ok(log)) => lines.extend(log.steps.iter().flat_map(test_log::step_lines)),
+        Some(Err(e)) => lines.push(e.to_string()),
+        None if execution.logs.is_empty() => lines.push("The server recorded no output".to_string()),
+        None => lines.extend(execution.logs.iter().map(|l| format!("  {}", l))),
+    }
+    if let Some(error) = &execution.error {
+        lines.push(format!("Error: {}", error));
+    }
+    lines
+}
+
+/// Starts the next queued test when nothing is running. The run streams its
+/// output back through the run channel.
+fn start_next(ctx: &Context<'_>, dashboard: &mut Dashboard) {
+    if dashboard.running.is_some() {
+        return;
+    }
+    let Some(test_id) = dashboard.queue.pop_front() else {
+        return;
+    };
+    dashboard.output.insert(test_id.clone(), Vec::new());
+    dashboard.running = Some((test_id.clone(), Instant::now()));
+
+    let client = ctx.client.clone();
+    let (url, repo) = (ctx.url.to_string(), ctx.repo.to_string());
+    let runs = ctx.runs.clone();
+    tokio::spawn(async move {
+        let lines = runs.clone();
+        let result = test_run::run_once(&client, &url, &repo, &test_id, |line| {
+            let _ = lines.send(RunEvent::Output(test_id.clone(), line.to_string()));
+        })
+        .await;
+        let _ = runs.send(RunEvent::Done(test_id, result));
+    });
+}
+
+async fn handle_run_event(ctx: &Context<'_>, dashboard: &mut Dashboard, event: RunEvent) {
+    match event {
+        RunEvent::Output(test_id, line) => dashboard.output.entry(test_id).or_default().push(line),
+        RunEvent::Done(test_id, result) => {
+            dashboard.running = None;
+            let name = dashboard
+                .row_mut(&test_id)
+                .map(|r| r.test.name.clone())
+                .unwrap_or_else(|| test_id.clone());
+            dashboard.message = Some(match result {
+                Ok(outcome) => {
+                    let status = outcome.status.unwrap_or_else(|| "unknown".to_string());
+                    let mark = if status == "passed" { "✓" } else { "✗" };
+                    (format!("{} {} {}", mark, name, status), status != "passed")
+                }
+                Err(e) => {
+                    dashboard.output.entry(test_id.clone()).or_default().push(format!("Error: {}", e));
+                    (format!("✗ {}: {}", name, e), true)
+                }
+            });
+            // The new execution brings the status, pass rate and duration up to date
+            if let Ok(executions) = api::fetch_executions(ctx.client, ctx.url, ctx.repo, &test_id).await
+                && let Some(row) = dashboard.row_mut(&test_id)
+            {
+                row.executions = executions;
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, repo: &str, dashboard: &Dashboard) {
+    let [table_area, detail_area, status] =
+        Layout::vertical([Constraint::Percentage(55), Constraint::Min(5), Constraint::Length(1)]).areas(frame.area());
+    draw_table(frame, table_area, dashboard);
+    draw_detail(frame, detail_area, dashboard);
+
+    let status_line = match &dashboard.message {
+        Some((message, true)) => Line::styled(message.clone(), Style::default().fg(Color::Red)),
+        Some((message, false)) => Line::styled(message.clone(), Style::default().fg(Color::Green)),
+        None => Line::from(vec![
+            Span::styled(format!(" {} ", repo), Style::default().add_modifier(Modifier::REVERSED)),
+            Span::styled(format!(" {}", HELP), Style::default().fg(Color::DarkGray)),
+        ]),
+    };
+    frame.render_widget(Paragraph::new(status_line), status);
+}
+
+fn draw_table(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let rows: Vec<Row> = dashboard
+        .entries
+        .iter()
+        .map(|entry| match entry {
+            Entry::Group(group) => {
+                let tests: Vec<&TestRow> = dashboard.rows.iter().filter(|r| r.group() == group).collect();
+                let failing = tests.iter().filter(|r| r.last().is_some_and(|e| e.status != "passed")).count();
+                let count = match tests.len() {
+                    1 => "1 test".to_string(),
+                    n => format!("{} tests", n),
+                };
+                let summary = match failing {
+                    0 => count,
+                    n => format!("{}, {} failing", count, n),
+                };
+                Row::new(vec![
+                    Cell::from(group.clone()).style(Style::default().add_modifier(Modifier::BOLD)),
+                    Cell::from(summary).style(Style::default().fg(if failing > 0 { Color::Red } else { Color::DarkGray })),
+                ])
+            }
+            Entry::Test(i) => test_row(dashboard, &dashboard.rows[*i]),
+        })
+        .collect();
+
+    let running = dashboard.queue.len() + usize::from(dashboard.running.is_some());
+    let title = match running {
+        0 => format!(" Tests ({}) ", dashboard.rows.len()),
+        n => format!(" Tests ({}) · {} running or queued ", dashboard.rows.len(), n),
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Length(17),
+        ],
+    )
+    .header(
+        Row::new(vec!["TEST", "STATUS", "PASS RATE", "DURATION", "LAST RUN"])
+            .style(Style::default().fg(Color::DarkGray)),
+    )
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().bg(Color::Cyan).fg(Color::Black));
+    let mut state = TableState::default().with_selected((!dashboard.entries.is_empty()).then_some(dashboard.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn test_row<'a>(dashboard: &Dashboard, row: &'a TestRow) -> Row<'a> {
+    let id = &row.test.id;
+    let status = match &dashboard.running {
+        Some((running, started)) if running == id => {
+            Cell::from(format!("● running {}s", started.elapsed().as_secs())).style(Style::default().fg(Color::Yellow))
+        }
+        _ if dashboard.queue.contains(id) => Cell::from("… queued").style(Style::default().fg(Color::Yellow)),
+        _ => match row.last() {
+            Some(e) if e.status == "passed" => Cell::from("✓ passed").style(Style::default().fg(Color::Green)),
+            Some(e) => Cell::from(format!("✗ {}", e.status)).style(Style::default().fg(Color::Red)),
+            None => Cell::from("-").style(Style::default().fg(Color::DarkGray)),
+        },
+    };
+    let pass_rate = row
+        .pass_rate()
+        .map(|rate| format!("{:.0}% of {}", rate * 100.0, row.executions.len()))
+        .unwrap_or_else(|| "-".to_string());
+    let duration = row
+        .last()
+        .and_then(|e| e.duration)
+        .map(|ms| test_stats::format_duration(ms as f64))
+        .unwrap_or_else(|| "-".to_string());
+    let last_run = row
+        .last()
+        .map(|e| match chrono::DateTime::parse_from_rfc3339(&e.started_at) {
+            Ok(started) => started.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+            Err(_) => e.started_at.clone(),
+        })
+        .unwrap_or_else(|| "-".to_string());
+    Row::new(vec![
+        Cell::from(format!("  {}", row.test.name)),
+        status,
+        Cell::from(pass_rate),
+        Cell::from(duration),
+        Cell::from(last_run),
+    ])
+}
+
+fn draw_detail(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let height = area.height.saturating_sub(2) as usize;
+    let (title, lines, scroll) = match (&dashboard.detail, dashboard.selected_row()) {
+        (_, None) => (" Group ".to_string(), vec!["Press r to run every test of the group".to_string()], 0),
+        (Detail::Steps(lines), Some(row)) => (format!(" Steps of {} ", row.test.name), lines.clone(), dashboard.scroll),
+        (Detail::Log(lines), Some(row)) => (format!(" Latest log of {} ", row.test.name), lines.clone(), dashboard.scroll),
+        (Detail::Summary, Some(row)) => match dashboard.output.get(&row.test.id) {
+            // Follow the output; scrolling goes back from the end
+            Some(output) => {
+                let end = output.len().saturating_sub(dashboard.scroll as usize);
+                let start = end.saturating_sub(height);
+                (format!(" Run output of {} ", row.test.name), output[start..end].to_vec(), 0)
+            }
+            None => (format!(" {} ", row.test.name), summary(row), dashboard.scroll),
+        },
+    };
+    let text: Vec<Line> = lines.into_iter().map(Line::from).collect();
+    let detail = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((scroll, 0));
+    frame.render_widget(detail, area);
+}
+
+/// Description and recent executions of a test.
+fn summary(row: &TestRow) -> Vec<String> {
+    let mut lines = vec![format!("{} ({})", row.test.id, row.group())];
+    if let Some(description) = row.test.description.as_deref().filter(|d| !d.is_empty()) {
+        lines.push(description.to_string());
+    }
+    lines.push(String::new());
+    if row.executions.is_empty() {
+        lines.push("No executions yet; press r to run".to_string());
+    }
+    for e in row.executions.iter().take(10) {
+        let duration = e.duration.map(|ms| test_stats::format_duration(ms as f64)).unwrap_or_default();
+        lines.push(format!("{:<8} {:<25} {:>7}  {}", e.status, e.started_at, duration, e.id));
+    }
+    lines
+}
@@ -1,15 +1,20 @@
+use crate::http::Dispatch;
+use crate::picker;
 use crate::types::EntityType;
 
 pub async fn execute(
     client: &reqwest::Client,
     url: &str,
-    _repo: &str,
+    repo: &str,
     entity: EntityType,
-    path: &str,
+    path: Option<String>,
     content: &str,
 ) -> anyhow::Result<()> {
     match entity {
-        EntityType::Doc => write_doc(client, url, path, content).await,
+        EntityType::Doc => {
+            let path = picker::doc(client, url, repo, path, "--path").await?;
+            write_doc(client, url, &path, content).await
+        }
         _ => {
             println!("Write command for {} not yet implemented", entity);
             Ok(())
@@ -26,7 +31,7 @@ async fn write_doc(client: &reqwest::Client, url: &str, path: &str, content: &st
     let resp = client
         .post(format!("{}/api/file", url))
         .json(&body)
-        .send()
+        .dispatch()
         .await?;
 
     if resp.status().is_success() {
@@ -0,0 +1,114 @@
+use crate::api;
+use crate::config::{self, Settings};
+use crate::types::ServerVersion;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Whether the server serves the API this CLI was written against. Servers list
+// their routes at `/api/version`; routes are named like their folders under
+// apps/web/src/app/api, without the route groups.
+
+pub const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Routes the CLI calls.
+const ROUTES: &[&str] = &[
+    "/api/agents",
+    "/api/api-keys",
+    "/api/app-status",
+    "/api/file",
+    "/api/files",
+    "/api/gateway/login",
+    "/api/repositories",
+    "/api/settings",
+    "/api/tasks",
+    "/api/tests",
+    "/api/tests/[id]",
+    "/api/tests/[id]/executions",
+    "/api/tests/[id]/finish",
+    "/api/tests/[id]/run",
+    "/api/tests/[id]/steps",
+    "/api/tests/artifacts/[...path]",
+    "/api/tests/groups",
+    "/api/tests/record/ai",
+    "/api/tests/record/capture",
+    "/api/tests/record/execute",
+];
+
+/// How long a server that passed the check on startup isn't checked again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How long a server that couldn't be reached isn't checked again, so that
+/// commands run while it is down don't each wait for the connect timeout.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Routes the CLI calls that the server doesn't serve.
+pub fn missing_routes(server: &ServerVersion) -> Vec<&'static str> {
+    ROUTES.iter().copied().filter(|route| !server.routes.iter().any(|r| r == route)).collect()
+}
+
+/// What may not work with the server, given its `/api/version` (`None` when it
+/// has none).
+pub fn problems(server: Option<&ServerVersion>) -> Vec<String> {
+    let Some(server) = server else {
+        return vec![format!(
+            "The server doesn't report its version, so it likely predates this CLI ({}); some commands may fail",
+            CLI_VERSION
+        )];
+    };
+    let missing = missing_routes(server);
+    if missing.is_empty() {
+        return Vec::new();
+    }
+    vec![format!(
+        "Agelum {} on the server lacks routes this CLI ({}) calls: {}",
+        server.version,
+        CLI_VERSION,
+        missing.join(", ")
+    )]
+}
+
+/// Warns on startup when the server lacks routes the CLI calls. Servers are
+/// checked at most once an hour; a server that can't be reached is left to the
+/// command to report, and not tried again for a few minutes.
+pub async fn check(client: &reqwest::Client, settings: &Settings) {
+    let path = cache_path(&settings.url, "json");
+    let failed_path = cache_path(&settings.url, "unreachable");
+    if is_fresh(path.as_deref(), CHECK_INTERVAL) || is_fresh(failed_path.as_deref(), RETRY_INTERVAL) {
+        return;
+    }
+    let Ok(server) = api::fetch_version(client, &settings.url).await else {
+        if let Some(path) = failed_path {
+            let _ = std::fs::create_dir_all(path.parent().unwrap_or(&path));
+            let _ = std::fs::write(&path, "");
+        }
+        return;
+    };
+    if let Some(path) = &failed_path {
+        let _ = std::fs::remove_file(path);
+    }
+
+    let problems = problems(server.as_ref());
+    for problem in &problems {
+        eprintln!("⚠ {}", problem);
+    }
+    if !problems.is_empty() {
+        eprintln!("  See `agelum status`; this is checked again in an hour");
+    }
+    if let Some(path) = path {
+        let _ = std::fs::create_dir_all(path.parent().unwrap_or(&path));
+        let _ = std::fs::write(&path, serde_json::to_string(&server).unwrap_or_default());
+    }
+}
+
+fn cache_path(url: &str, extension: &str) -> Option<PathBuf> {
+    let key = url.trim_end_matches('/');
+    let home = config::agelum_home().ok()?;
+    Some(home.join("cache").join("servers").join(format!("{:x}.{}", Sha256::digest(key), extension)))
+}
+
+/// Whether the file was written less than `interval` ago.
+fn is_fresh(path: Option<&Path>, interval: Duration) -> bool {
+    path.and_then(|path| path.metadata().ok()?.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < interval)
+}
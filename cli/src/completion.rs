@@ -0,0 +1,269 @@
+use crate::api;
+use crate::config::{self, Settings};
+use crate::picker;
+use crate::schedule::{self, ScheduleStore};
+use crate::types::{Repository, TASK_STATES};
+use clap::Command;
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+// Shell completion. The scripts printed by `agelum completions <shell>` call the
+// CLI back with `AGELUM_COMPLETE` set; values that live on the server (repos,
+// tests, tasks, docs) are fetched then and cached for a short while, so that
+// repeated tabs don't wait on the server.
+
+pub const ENV_VAR: &str = "AGELUM_COMPLETE";
+const CACHE_TTL: Duration = Duration::from_secs(30);
+/// Completion gives up on a slow server rather than hang the shell.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const ENTITIES: [&str; 7] = ["epic", "task", "idea", "doc", "tool", "testgroup", "test"];
+
+/// Adds value completers to the arguments of every subcommand, by argument name.
+pub fn with_completers(cmd: Command) -> Command {
+    cmd.mut_args(|arg| match arg.get_id().as_str() {
+        "repo" => arg.add(ArgValueCandidates::new(repos)),
+        "entity" => arg.add(ArgValueCandidates::new(entities)),
+        "test_id" => arg.add(ArgValueCandidates::new(tests)),
+        "id" => arg.add(ArgValueCandidates::new(ids)),
+        "target" => arg.add(ArgValueCandidates::new(targets)),
+        "state" | "from_state" | "to_state" => arg.add(ArgValueCandidates::new(states)),
+        "path" => arg.add(ArgValueCandidates::new(docs)),
+        _ => arg,
+    })
+    .mut_subcommands(with_completers)
+}
+
+fn repos() -> Vec<CompletionCandidate> {
+    candidates(repositories())
+}
+
+/// Names and paths of the server's repositories.
+fn repositories() -> Vec<(String, String)> {
+    let (client, url) = server();
+    cached_values(&url, "repos", None, async {
+        let repositories = api::fetch_repositories(&client, &url).await?;
+        Ok(repositories.into_iter().map(|r| (r.name, r.path)).collect())
+    })
+}
+
+fn entities() -> Vec<CompletionCandidate> {
+    ENTITIES.iter().map(CompletionCandidate::new).collect()
+}
+
+fn tests() -> Vec<CompletionCandidate> {
+    let (client, url) = server();
+    let Some(repo) = repo() else {
+        return Vec::new();
+    };
+    cached(&url, "tests", Some(&repo), async {
+        let tests = api::fetch_tests(&client, &url, &repo).await?;
+        Ok(tests
+            .into_iter()
+            .map(|t| (t.id, format!("{} / {}", t.group.as_deref().unwrap_or("-"), t.name)))
+            .collect())
+    })
+}
+
+fn tasks() -> Vec<CompletionCandidate> {
+    let (client, url) = server();
+    let Some(repo) = repo() else {
+        return Vec::new();
+    };
+    cached(&url, "tasks", Some(&repo), async {
+        let tasks = api::fetch_tasks(&client, &url, &repo).await?;
+        Ok(tasks.into_iter().map(|t| (t.id, format!("{} [{}]", t.title, t.state))).collect())
+    })
+}
+
+fn groups() -> Vec<CompletionCandidate> {
+    let (client, url) = server();
+    let Some(repo) = repo() else {
+        return Vec::new();
+    };
+    cached(&url, "groups", Some(&repo), async {
+        let mut groups: Vec<String> = api::fetch_tests(&client, &url, &repo)
+            .await?
+            .into_iter()
+            .filter_map(|t| t.group)
+            .collect();
+        groups.sort();
+        groups.dedup();
+        Ok(groups.into_iter().map(|g| (g, "test group".to_string())).collect())
+    })
+}
+
+fn docs() -> Vec<CompletionCandidate> {
+    let (client, url) = server();
+    let Some(repo) = repo() else {
+        return Vec::new();
+    };
+    cached(&url, "docs", Some(&repo), async {
+        let files = api::fetch_files(&client, &url, &repo, "doc").await?;
+        let mut paths = Vec::new();
+        let mut pending: Vec<_> = files.tree.into_iter().collect();
+        while let Some(node) = pending.pop() {
+            if node.kind == "file" && node.path.ends_with(".md") {
+                paths.push((node.path, String::new()));
+            }
+            pending.extend(node.children);
+        }
+        paths.sort();
+        Ok(paths)
+    })
+}
+
+/// `--id` names a task or a test depending on `--entity`; without an entity it's
+/// a schedule id (`test schedule rm`).
+fn ids() -> Vec<CompletionCandidate> {
+    match flag("entity").as_deref() {
+        Some("task") => tasks(),
+        Some("test") => tests(),
+        Some("testgroup") => groups(),
+        Some(_) => Vec::new(),
+        None => schedule::schedule_path()
+            .and_then(|path| ScheduleStore::load(&path))
+            .map(|store| {
+                store
+                    .schedules
+                    .into_iter()
+                    .map(|s| CompletionCandidate::new(s.id).help(Some(format!("{} every {}", s.target, s.every).into())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Schedule targets are tests or groups.
+fn targets() -> Vec<CompletionCandidate> {
+    let mut candidates = tests();
+    candidates.extend(groups());
+    candidates
+}
+
+/// Task states, or group names where `--state` holds the group of a test.
+fn states() -> Vec<CompletionCandidate> {
+    if flag("entity").as_deref() == Some("test") {
+        return groups();
+    }
+    TASK_STATES.iter().map(CompletionCandidate::new).collect()
+}
+
+/// Value of a flag on the command line being completed.
+fn flag(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let long = format!("--{}", name);
+    let prefix = format!("--{}=", name);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if *arg == long {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(String::from)
+        }
+    })
+}
+
+/// Settings of the command line being completed; a broken profile completes
+/// against the defaults.
+fn settings() -> Option<Settings> {
+    Settings::resolve(flag("profile").as_deref(), flag("url")).ok()
+}
+
+fn server() -> (reqwest::Client, String) {
+    let settings = settings();
+    let client = settings
+        .as_ref()
+        .and_then(|s| s.authorize().ok().map(|_| s.client_builder()))
+        .unwrap_or_default()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let url = settings
+        .map(|s| s.url)
+        .or_else(|| flag("url"))
+        .unwrap_or_else(|| config::DEFAULT_URL.to_string());
+    (client, url)
+}
+
+/// `--repo`, else `AGELUM_REPO`, else the repository checked out in the working
+/// directory, else the profile's default one.
+fn repo() -> Option<String> {
+    if let Some(repo) = flag("repo") {
+        return Some(repo);
+    }
+    let settings = settings();
+    if let Some(settings) = &settings
+        && settings.repo_from_env
+    {
+        return settings.repo.clone();
+    }
+    if let Some(root) = std::env::current_dir().ok().and_then(|dir| picker::checkout_root(&dir)) {
+        let repositories: Vec<Repository> = repositories()
+            .into_iter()
+            .map(|(name, path)| Repository { name, path, tests_dir: None })
+            .collect();
+        if let Some(repository) = picker::find_checkout(&root, &repositories) {
+            return Some(repository.name.clone());
+        }
+    }
+    settings?.repo
+}
+
+/// Candidates for values with their descriptions, see [`cached_values`].
+fn cached(
+    url: &str,
+    kind: &str,
+    repo: Option<&str>,
+    load: impl Future<Output = anyhow::Result<Vec<(String, String)>>>,
+) -> Vec<CompletionCandidate> {
+    candidates(cached_values(url, kind, repo, load))
+}
+
+fn candidates(values: Vec<(String, String)>) -> Vec<CompletionCandidate> {
+    values
+        .into_iter()
+        .map(|(value, help)| {
+            let help = (!help.is_empty()).then(|| help.into());
+            CompletionCandidate::new(value).help(help)
+        })
+        .collect()
+}
+
+/// Values with their descriptions, from the cache while it's fresh, otherwise
+/// from `load`. Errors give no values instead of noise in the shell.
+fn cached_values(
+    url: &str,
+    kind: &str,
+    repo: Option<&str>,
+    load: impl Future<Output = anyhow::Result<Vec<(String, String)>>>,
+) -> Vec<(String, String)> {
+    let key = format!("{}\n{}\n{}", url, kind, repo.unwrap_or_default());
+    let path = config::agelum_home()
+        .ok()
+        .map(|home| home.join("cache").join("completions").join(format!("{:x}.json", Sha256::digest(&key))));
+
+    let fresh = path.as_ref().and_then(|path| {
+        let age = SystemTime::now().duration_since(path.metadata().ok()?.modified().ok()?).ok()?;
+        if age > CACHE_TTL {
+            return None;
+        }
+        serde_json::from_str::<Vec<(String, String)>>(&std::fs::read_to_string(path).ok()?).ok()
+    });
+    match fresh {
+        Some(values) => values,
+        None => {
+            // Completion runs inside the CLI's runtime, before any command is parsed
+            let Ok(values) = tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(load)) else {
+                return Vec::new();
+            };
+            if let Some(path) = &path
+                && let Some(dir) = path.parent()
+                && std::fs::create_dir_all(dir).is_ok()
+            {
+                let _ = std::fs::write(path, serde_json::to_string(&values).unwrap_or_default());
+            }
+            values
+        }
+    }
+}
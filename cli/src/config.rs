@@ -0,0 +1,348 @@
+use crate::credentials::{self, CredentialStore};
+use crate::http;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// Files the CLI keeps for the user, outside any repository.
+
+/// Server used when `--url` is not given.
+pub const DEFAULT_URL: &str = "http://localhost:6500";
+const CONFIG_FILE: &str = "cli.toml";
+/// How long connecting to the server may take.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the server may go without sending anything; long enough for slow
+/// routes, and for test runs between two lines of output.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Retries of GET requests that failed to connect or got a 502/503.
+pub const DEFAULT_RETRIES: u32 = 2;
+/// Environment variables that override the profile's value of the same key.
+pub const ENV_OVERRIDES: [&str; 8] = [
+    "AGELUM_URL",
+    "AGELUM_REPO",
+    "AGELUM_OUTPUT",
+    "AGELUM_TOKEN",
+    "AGELUM_LOCAL_PATH",
+    "AGELUM_CONNECT_TIMEOUT",
+    "AGELUM_TIMEOUT",
+    "AGELUM_RETRIES",
+];
+
+/// `~/.agelum`, or `$AGELUM_HOME` when set.
+pub fn agelum_home() -> anyhow::Result<PathBuf> {
+    if let Some(home) = std::env::var_os("AGELUM_HOME").filter(|h| !h.is_empty()) {
+        return Ok(PathBuf::from(home));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".agelum"))
+        .ok_or_else(|| anyhow::anyhow!("Could not find the home directory; set AGELUM_HOME"))
+}
+
+pub fn config_path() -> anyhow::Result<PathBuf> {
+    Ok(agelum_home()?.join(CONFIG_FILE))
+}
+
+/// `~/.agelum/cli.toml`:
+///
+/// ```toml
+/// default_profile = "local"
+///
+/// [profiles.local]
+/// repo = "agelum"
+///
+/// [profiles.team]
+/// url = "https://agelum.example.com"
+/// token = "ak_..."
+/// output = "json"
+/// local_path = "/home/me/code"
+/// timeout = "5m"
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    /// Profile used when neither `--profile` nor `AGELUM_PROFILE` is set
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub url: Option<String>,
+    /// Used when `--repo` is not given
+    pub repo: Option<String>,
+    pub output: Option<OutputFormat>,
+    /// Sent as a bearer token with every request
+    pub token: Option<String>,
+    /// Where this machine keeps the server's repositories, when the server runs
+    /// elsewhere; local runs, logs and test specs are read there instead of
+    /// under the server's base path.
+    pub local_path: Option<PathBuf>,
+    /// e.g. `10s`; `0` for no limit
+    pub connect_timeout: Option<String>,
+    /// How long the server may go without sending anything, e.g. `2m`; `0`
+    /// for no limit
+    pub timeout: Option<String>,
+    pub retries: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl CliConfig {
+    /// Reads the config file; a missing file has no profiles.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(CliConfig::default());
+        }
+        toml::from_str(&std::fs::read_to_string(path)?).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Name of the profile in use: `--profile`, else `AGELUM_PROFILE`, else
+    /// `default_profile`.
+    pub fn selected(&self, flag: Option<&str>) -> Option<String> {
+        flag.map(String::from)
+            .or_else(|| env("AGELUM_PROFILE"))
+            .or_else(|| self.default_profile.clone())
+    }
+
+    /// Problems that make a profile unusable, or likely not what was meant.
+    pub fn validate(&self, name: &str) -> Vec<String> {
+        let Some(profile) = self.profiles.get(name) else {
+            return vec![format!("No profile named {}", name)];
+        };
+        let mut problems = Vec::new();
+        if let Some(url) = &profile.url {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                Ok(parsed) => problems.push(format!("url: unsupported scheme {}", parsed.scheme())),
+                Err(e) => problems.push(format!("url: {}", e)),
+            }
+        }
+        if profile.repo.as_deref().is_some_and(|r| r.trim().is_empty()) {
+            problems.push("repo: empty".to_string());
+        }
+        if profile.token.as_deref().is_some_and(|t| t.trim().is_empty()) {
+            problems.push("token: empty".to_string());
+        }
+        if let Some(path) = &profile.local_path
+            && !path.is_dir()
+        {
+            problems.push(format!("local_path: {} is not a directory", path.display()));
+        }
+        for (key, value) in [("connect_timeout", &profile.connect_timeout), ("timeout", &profile.timeout)] {
+            if let Some(value) = value
+                && let Err(e) = parse_timeout(value)
+            {
+                problems.push(format!("{}: {}", key, e));
+            }
+        }
+        problems
+    }
+}
+
+/// Settings a command runs with, from (first wins) the command-line flags,
+/// `AGELUM_<KEY>` environment variables, the selected profile, and the defaults.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub url: String,
+    pub repo: Option<String>,
+    /// Whether `repo` comes from `AGELUM_REPO` rather than the profile
+    pub repo_from_env: bool,
+    pub output: OutputFormat,
+    pub token: Option<String>,
+    /// Where the token comes from
+    pub token_source: Option<TokenSource>,
+    pub local_path: Option<PathBuf>,
+    /// `None` for no limit
+    pub connect_timeout: Option<Duration>,
+    /// `None` for no limit
+    pub timeout: Option<Duration>,
+    pub retries: u32,
+    /// Log every request to stderr
+    pub verbose: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenSource {
+    Environment,
+    Profile,
+    /// Saved by `agelum auth` for the server
+    Credentials,
+}
+
+static CURRENT: OnceLock<Settings> = OnceLock::new();
+
+impl Settings {
+    /// Resolves the settings of the selected profile (see [`CliConfig::selected`])
+    /// for the server at `url`, or the profile's. Without a token in the
+    /// environment or the profile, the one saved by `agelum auth` for the server
+    /// is used.
+    pub fn resolve(profile: Option<&str>, url: Option<String>) -> anyhow::Result<Settings> {
+        let path = config_path()?;
+        let config = CliConfig::load(&path)?;
+        let name = config.selected(profile);
+        let selected = match &name {
+            Some(name) => config.profiles.get(name).cloned().ok_or_else(|| {
+                let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+                anyhow::anyhow!(
+                    "Unknown profile {}; {} has: {}",
+                    name,
+                    path.display(),
+                    if known.is_empty() { "none".to_string() } else { known.join(", ") }
+                )
+            })?,
+            None => Profile::default(),
+        };
+
+        let output = match env("AGELUM_OUTPUT") {
+            Some(output) => <OutputFormat as clap::ValueEnum>::from_str(&output, true)
+                .map_err(|_| anyhow::anyhow!("Invalid AGELUM_OUTPUT {:?}; expected text or json", output))?,
+            None => selected.output.unwrap_or_default(),
+        };
+        let url = url
+            .or_else(|| env("AGELUM_URL"))
+            .or(selected.url)
+            .unwrap_or_else(|| DEFAULT_URL.to_string());
+        let (token, token_source) = match (env("AGELUM_TOKEN"), selected.token) {
+            (Some(token), _) => (Some(token), Some(TokenSource::Environment)),
+            (None, Some(token)) => (Some(token), Some(TokenSource::Profile)),
+            (None, None) => {
+                let store = CredentialStore::load(&credentials::credentials_path()?)?;
+                match store.get(&url) {
+                    Some(credential) => (Some(credential.token.clone()), Some(TokenSource::Credentials)),
+                    None => (None, None),
+                }
+            }
+        };
+        let connect_timeout = match env("AGELUM_CONNECT_TIMEOUT").or(selected.connect_timeout) {
+            Some(value) => {
+                parse_timeout(&value).map_err(|e| anyhow::anyhow!("Invalid connect timeout {:?}: {}", value, e))?
+            }
+            None => Some(DEFAULT_CONNECT_TIMEOUT),
+        };
+        let timeout = match env("AGELUM_TIMEOUT").or(selected.timeout) {
+            Some(value) => parse_timeout(&value).map_err(|e| anyhow::anyhow!("Invalid timeout {:?}: {}", value, e))?,
+            None => Some(DEFAULT_TIMEOUT),
+        };
+        let retries = match env("AGELUM_RETRIES") {
+            Some(value) => value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid AGELUM_RETRIES {:?}; expected a number", value))?,
+            None => selected.retries.unwrap_or(DEFAULT_RETRIES),
+        };
+        Ok(Settings {
+            url,
+            repo_from_env: env("AGELUM_REPO").is_some(),
+            repo: env("AGELUM_REPO").or(selected.repo),
+            output,
+            token,
+            token_source,
+            local_path: env("AGELUM_LOCAL_PATH").map(PathBuf::from).or(selected.local_path),
+            connect_timeout,
+            timeout,
+            retries,
+            verbose: env("AGELUM_VERBOSE").is_some_and(|v| v != "0" && v != "false"),
+        })
+    }
+
+    /// Makes these the settings of the whole process, for the few places that
+    /// can't be handed them.
+    pub fn install(self) -> &'static Settings {
+        CURRENT.get_or_init(|| self)
+    }
+
+    /// HTTP client for the server; requests sent with
+    /// [`Dispatch`](crate::http::Dispatch) carry the token, if there is one.
+    pub fn client(&self) -> anyhow::Result<reqwest::Client> {
+        self.authorize()?;
+        Ok(self.client_builder().build()?)
+    }
+
+    /// The settings for the server at `url`. A token from the environment or the
+    /// profile belongs to the server of these settings, so another server only
+    /// gets the credentials saved for it.
+    pub fn for_server(&self, url: &str) -> anyhow::Result<Settings> {
+        let mut settings = self.clone();
+        if url.trim_end_matches('/') == self.url.trim_end_matches('/') {
+            return Ok(settings);
+        }
+        let store = CredentialStore::load(&credentials::credentials_path()?)?;
+        settings.url = url.to_string();
+        (settings.token, settings.token_source) = match store.get(url) {
+            Some(credential) => (Some(credential.token.clone()), Some(TokenSource::Credentials)),
+            None => (None, None),
+        };
+        Ok(settings)
+    }
+
+    /// Makes requests to the server, and only to it, carry the token.
+    pub fn authorize(&self) -> anyhow::Result<()> {
+        match &self.token {
+            Some(token) => http::authorize(&self.url, token),
+            None => Ok(()),
+        }
+    }
+
+    /// HTTP client that gives up on a server that doesn't answer. It carries no
+    /// credentials, so it may also be used for other hosts.
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        // A limit per read rather than for the whole request, so streamed test
+        // runs aren't cut off while they keep sending output
+        if let Some(timeout) = self.timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        builder
+    }
+}
+
+/// The settings installed for the process, if any.
+pub fn current() -> Option<&'static Settings> {
+    CURRENT.get()
+}
+
+/// A duration such as `30s` or `2m`; `0` for no limit.
+pub fn parse_timeout(value: &str) -> anyhow::Result<Option<Duration>> {
+    let value = value.trim();
+    if value == "0" {
+        return Ok(None);
+    }
+    let duration = humantime::parse_duration(value)?;
+    Ok((!duration.is_zero()).then_some(duration))
+}
+
+/// The local counterpart of a repository path under the server's `base_path`,
+/// when the selected profile sets `local_path`.
+pub fn local_repository_path(path: &str, base_path: &str) -> Option<PathBuf> {
+    let local = CURRENT.get()?.local_path.as_ref()?;
+    if base_path.is_empty() {
+        return None;
+    }
+    let relative = Path::new(path).strip_prefix(base_path).ok()?;
+    Some(local.join(relative))
+}
+
+/// Shows enough of a secret to tell which one it is.
+pub fn mask(secret: &str) -> String {
+    let visible: String = secret.chars().take(4).collect();
+    if secret.chars().count() <= 8 {
+        return "****".to_string();
+    }
+    format!("{}****", visible)
+}
+
+fn env(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|v| !v.is_empty())
+}
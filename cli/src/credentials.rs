@@ -0,0 +1,108 @@
+use crate::config;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Tokens saved by `agelum auth`, one per server, in a file only the user can
+// read.
+
+const CREDENTIALS_FILE: &str = "credentials.json";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CredentialStore {
+    /// By server URL
+    #[serde(default)]
+    pub servers: BTreeMap<String, Credential>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Credential {
+    pub token: String,
+    pub kind: CredentialKind,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub saved_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CredentialKind {
+    /// Token from the gateway login (`agelum auth login`)
+    Gateway,
+    /// API key set with `agelum auth token set`
+    ApiKey,
+}
+
+pub fn credentials_path() -> anyhow::Result<PathBuf> {
+    Ok(config::agelum_home()?.join(CREDENTIALS_FILE))
+}
+
+/// `http://localhost:6500/` and `http://localhost:6500` are the same server.
+fn server_key(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+impl CredentialStore {
+    /// Reads the credentials; a missing file has none.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(CredentialStore::default());
+        }
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Writes the credentials through a temporary file that is created readable
+    /// by the user only, so the tokens are never visible to others.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        use std::io::Write;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let _ = std::fs::remove_file(&tmp);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, url: &str) -> Option<&Credential> {
+        self.servers.get(&server_key(url))
+    }
+
+    pub fn set(&mut self, url: &str, credential: Credential) {
+        self.servers.insert(server_key(url), credential);
+    }
+
+    pub fn remove(&mut self, url: &str) -> Option<Credential> {
+        self.servers.remove(&server_key(url))
+    }
+}
+
+/// Expiry of a gateway token, read from its (unverified) JWT claims.
+pub fn expires_at(token: &str) -> Option<DateTime<Utc>> {
+    #[derive(Deserialize)]
+    struct Claims {
+        exp: i64,
+    }
+    let payload = token.split('.').nth(1)?;
+    let claims: Claims =
+        serde_json::from_slice(&base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    DateTime::from_timestamp(claims.exp, 0)
+}
@@ -0,0 +1,253 @@
+use crate::config::{self, Settings};
+use reqwest::header::{AUTHORIZATION, HeaderValue};
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode, Url};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Sending requests to the server: requests carry the token of the server they
+// go to, GETs are retried on connection errors and 502/503 (the app restarting,
+// or a proxy in front of it), failures to connect are explained, and with
+// `--verbose` every request is logged to stderr.
+
+/// Delay before the first retry; doubled for each one after it.
+const BACKOFF_BASE: Duration = Duration::from_millis(250);
+const BACKOFF_MAX: Duration = Duration::from_secs(4);
+
+/// `Authorization` values by server URL. Kept apart from the clients so that
+/// webhooks, absolute artifact URLs and other hosts never see a token.
+static TOKENS: Mutex<Vec<(Url, HeaderValue)>> = Mutex::new(Vec::new());
+
+/// Sends `token` as a bearer token with the requests to `server` and the paths
+/// under it.
+pub fn authorize(server: &str, token: &str) -> anyhow::Result<()> {
+    let server = Url::parse(server).map_err(|e| anyhow::anyhow!("Invalid server URL {}: {}", server, e))?;
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| anyhow::anyhow!("The token contains characters not allowed in a header"))?;
+    value.set_sensitive(true);
+    let mut tokens = TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+    tokens.retain(|(url, _)| *url != server);
+    tokens.push((server, value));
+    Ok(())
+}
+
+/// The token of the server `url` belongs to: same origin, and a path under the
+/// server's.
+fn token_for(url: &Url) -> Option<HeaderValue> {
+    let tokens = TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+    tokens
+        .iter()
+        .filter(|(server, _)| server.origin() == url.origin() && is_under(url.path(), server.path()))
+        // The most specific server wins
+        .max_by_key(|(server, _)| server.path().len())
+        .map(|(_, value)| value.clone())
+}
+
+fn is_under(path: &str, base: &str) -> bool {
+    let base = base.trim_end_matches('/');
+    path.strip_prefix(base).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+pub trait Dispatch {
+    /// Sends the request like [`RequestBuilder::send`], with the retries,
+    /// logging and diagnostics of the installed settings. Without installed
+    /// settings (shell completion), requests are sent once and not logged.
+    fn dispatch(self) -> impl Future<Output = anyhow::Result<Response>>;
+
+    /// Like [`Dispatch::dispatch`], but never retried; for checks whose failure
+    /// is better left to the requests that follow.
+    fn dispatch_once(self) -> impl Future<Output = anyhow::Result<Response>>;
+}
+
+impl Dispatch for RequestBuilder {
+    async fn dispatch(self) -> anyhow::Result<Response> {
+        let retries = config::current().map_or(0, |s| s.retries);
+        dispatch(self, retries).await
+    }
+
+    async fn dispatch_once(self) -> anyhow::Result<Response> {
+        dispatch(self, 0).await
+    }
+}
+
+async fn dispatch(builder: RequestBuilder, retries: u32) -> anyhow::Result<Response> {
+    let settings = config::current();
+    let verbose = settings.is_some_and(|s| s.verbose);
+    let (client, request) = builder.build_split();
+    let mut request = request?;
+    if !request.headers().contains_key(AUTHORIZATION)
+        && let Some(token) = token_for(request.url())
+    {
+        request.headers_mut().insert(AUTHORIZATION, token);
+    }
+    let retries = match *request.method() {
+        Method::GET | Method::HEAD => retries,
+        _ => 0,
+    };
+
+    for attempt in 1..=retries {
+        // Bodies that are streams can't be sent twice
+        let Some(clone) = request.try_clone() else {
+            break;
+        };
+        let reason = match send(&client, clone, verbose).await {
+            Ok(resp) if matches!(resp.status(), StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE) => {
+                resp.status().to_string()
+            }
+            Err(e) if e.is_connect() || e.is_timeout() => root_cause(&e),
+            Ok(resp) => return Ok(resp),
+            Err(e) => return Err(diagnose(e, settings)),
+        };
+        let delay = backoff(attempt);
+        if verbose {
+            eprintln!("↻ {}; retry {} of {} in {}", reason, attempt, retries, millis(delay));
+        }
+        tokio::time::sleep(delay).await;
+    }
+    send(&client, request, verbose).await.map_err(|e| diagnose(e, settings))
+}
+
+async fn send(client: &Client, request: Request, verbose: bool) -> reqwest::Result<Response> {
+    let method = request.method().clone();
+    let url = request.url().clone();
+    if verbose {
+        eprintln!("→ {} {}", method, url);
+    }
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    if verbose {
+        let elapsed = millis(started.elapsed());
+        match &result {
+            Ok(resp) => eprintln!("← {} {} {} in {}", resp.status(), method, url.path(), elapsed),
+            Err(e) => eprintln!("✗ {} {} after {}: {}", method, url.path(), elapsed, root_cause(e)),
+        }
+    }
+    result
+}
+
+/// Exponential backoff with jitter, so that CLIs started together (as in CI)
+/// don't retry in step.
+fn backoff(attempt: u32) -> Duration {
+    let full = BACKOFF_BASE.saturating_mul(1 << (attempt - 1).min(8)).min(BACKOFF_MAX);
+    full.mul_f64(rand::random_range(0.5..=1.0))
+}
+
+/// Turns failures to reach the server into an explanation of what to do.
+fn diagnose(e: reqwest::Error, settings: Option<&Settings>) -> anyhow::Error {
+    let server = e
+        .url()
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_else(|| settings.map_or(config::DEFAULT_URL, |s| s.url.as_str()).to_string());
+    if e.is_connect() {
+        anyhow::anyhow!(
+            "Could not connect to the Agelum server at {} ({})\n  \
+             Start the app with `pnpm web:dev` or open the desktop app, \
+             or point the CLI at a running server with --url or --profile",
+            server,
+            root_cause(&e)
+        )
+    } else if e.is_timeout() {
+        let limit = settings.and_then(|s| s.timeout).map(|t| humantime::format_duration(t).to_string());
+        anyhow::anyhow!(
+            "The Agelum server at {} did not answer within {}\n  \
+             Raise the limit with --timeout (0 for none) if the request is expected to take longer",
+            server,
+            limit.as_deref().unwrap_or("the time limit")
+        )
+    } else {
+        e.into()
+    }
+}
+
+/// The innermost error, e.g. "Connection refused (os error 111)" rather than
+/// "error sending request for url (...)".
+fn root_cause(e: &reqwest::Error) -> String {
+    let mut cause: &dyn std::error::Error = e;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum_with_jitter() {
+        for (attempt, full) in [(1, 250), (2, 500), (3, 1000), (4, 2000), (5, 4000), (6, 4000), (40, 4000)] {
+            let full = Duration::from_millis(full);
+            for _ in 0..20 {
+                let delay = backoff(attempt);
+                assert!(delay >= full / 2 && delay <= full, "attempt {}: {:?}", attempt, delay);
+            }
+        }
+    }
+
+    /// A server that answers every connection with `status` and counts them.
+    async fn server(status: u16) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let hits = std::sync::Arc::new(AtomicUsize::new(0));
+        let counted = hits.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counted.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).await;
+                let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, hits)
+    }
+
+    #[tokio::test]
+    async fn retries_gets_on_unavailable_servers() {
+        use std::sync::atomic::Ordering;
+        let client = Client::new();
+
+        for (status, attempts) in [(503, 3), (502, 3), (500, 1), (404, 1), (200, 1)] {
+            let (url, hits) = server(status).await;
+            let resp = dispatch(client.get(&url), 2).await.unwrap();
+            assert_eq!(resp.status().as_u16(), status);
+            assert_eq!(hits.load(Ordering::SeqCst), attempts, "GET answered with {}", status);
+        }
+
+        // Requests that change data are sent once
+        let (url, hits) = server(503).await;
+        dispatch(client.post(&url), 2).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn explains_connection_failures() {
+        // Bound, then dropped, so nothing listens on it
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let error = dispatch(Client::new().get(format!("http://{}/api/version", addr)), 0).await.unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with(&format!("Could not connect to the Agelum server at http://{}", addr)), "{}", message);
+        assert!(message.contains("--url or --profile"), "{}", message);
+    }
+
+    #[test]
+    fn tokens_only_go_to_their_server() {
+        let url = |u: &str| Url::parse(u).unwrap();
+        authorize("http://tokens.test:6500", "outer").unwrap();
+        authorize("http://tokens.test:6500/team", "inner").unwrap();
+
+        let token = |u: &str| token_for(&url(u)).map(|v| v.to_str().unwrap().to_string());
+        assert_eq!(token("http://tokens.test:6500/api/tests").as_deref(), Some("Bearer outer"));
+        assert_eq!(token("http://tokens.test:6500/team/api/tests").as_deref(), Some("Bearer inner"));
+        assert_eq!(token("http://tokens.test:6500/teams/api").as_deref(), Some("Bearer outer"));
+        assert_eq!(token("http://tokens.test:6501/api/tests"), None);
+        assert_eq!(token("https://tokens.test:6500/api/tests"), None);
+        assert_eq!(token("http://hooks.test/api/tests"), None);
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::process::Command;
+
+// Prompts for commands that need the user's input on the terminal.
+
+/// Asks a yes/no question; an empty answer picks `default`.
+pub fn confirm(question: &str, default: bool) -> anyhow::Result<bool> {
+    print!("{} {} ", question, if default { "[Y/n]" } else { "[y/N]" });
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "" => default,
+        a => a == "y" || a == "yes",
+    })
+}
+
+/// Whether the user can answer prompts: stdin is a terminal, and so is stderr,
+/// which prompts are drawn on.
+pub fn is_interactive() -> bool {
+    use std::io::IsTerminal;
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Fails with a clear message when a missing argument can't be picked
+/// interactively.
+pub fn require(flag: &str) -> anyhow::Result<()> {
+    if !is_interactive() {
+        anyhow::bail!("Missing {}; pass it, or run in an interactive terminal to pick one from a list", flag);
+    }
+    Ok(())
+}
+
+/// Lets the user fuzzy-search the items and returns the index of the chosen one.
+pub fn fuzzy_select(prompt: &str, items: &[&str]) -> anyhow::Result<usize> {
+    let theme = dialoguer::theme::ColorfulTheme::default();
+    dialoguer::FuzzySelect::with_theme(&theme)
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .max_length(15)
+        .interact_opt()?
+        .ok_or_else(|| anyhow::anyhow!("Cancelled"))
+}
+
+/// Opens a file in `$VISUAL`/`$EDITOR` and waits for the editor to exit.
+pub fn open_editor(path: &std::path::Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often configured with arguments, e.g. "code --wait"
+    let mut words = shell_words::split(&editor)?;
+    if words.is_empty() {
+        anyhow::bail!("$EDITOR is empty");
+    }
+    let program = words.remove(0);
+
+    let status = Command::new(&program).args(words).arg(path).status()?;
+    if !status.success() {
+        anyhow::bail!("Editor {} exited with status: {}", program, status);
+    }
+    Ok(())
+}
@@ -1,16 +1,53 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use std::path::PathBuf;
 
+mod agent;
+mod agent_browser;
+mod api;
 mod commands;
+mod compat;
+mod completion;
+mod config;
+mod credentials;
+mod http;
+mod interactive;
+mod picker;
+mod runner;
+mod schedule;
+mod tui;
 mod types;
+mod variables;
 
+use commands::test_diff::DiffMetric;
 use types::EntityType;
 
 #[derive(Parser)]
 #[command(name = "agelum")]
 #[command(about = "CLI for Agelum Notes", long_about = None)]
 struct Cli {
-    #[arg(long, default_value = "http://localhost:6500")]
-    url: String,
+    /// Server URL [default: the profile's, or http://localhost:6500]
+    #[arg(long)]
+    url: Option<String>,
+
+    /// Profile from ~/.agelum/cli.toml [env: AGELUM_PROFILE]
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Output format of list commands [default: the profile's, or text]
+    #[arg(long)]
+    output: Option<config::OutputFormat>,
+
+    /// Time to connect to the server, e.g. 10s; 0 for no limit [default: the profile's, or 5s]
+    #[arg(long, value_name = "DURATION")]
+    connect_timeout: Option<String>,
+
+    /// Time the server may go without sending anything, e.g. 5m; 0 for no limit [default: the profile's, or 2m]
+    #[arg(long, value_name = "DURATION")]
+    timeout: Option<String>,
+
+    /// Log every request to the server, and its response, to stderr [env: AGELUM_VERBOSE]
+    #[arg(long, short)]
+    verbose: bool,
 
     #[command(subcommand)]
     command: Commands,
@@ -18,13 +55,17 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Show whether the server answers, its version, mode and running apps, and
+    /// whether it serves the routes this CLI calls
+    Status,
+
     /// List available repositories
     ListRepos,
     
     /// List entities (epic, task, idea, doc, tool)
     List {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
     },
@@ -32,7 +73,7 @@ enum Commands {
     /// Create a new entity
     Create {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
         #[arg(long)]
@@ -46,11 +87,11 @@ enum Commands {
     /// Move an entity (e.g., change task state)
     Move {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
         #[arg(long)]
-        id: String,
+        id: Option<String>,
         #[arg(long)]
         from_state: String,
         #[arg(long)]
@@ -60,39 +101,47 @@ enum Commands {
     /// Read an entity's content
     Read {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
         #[arg(long)]
-        path: String,
+        path: Option<String>,
     },
     
     /// Write content to an entity
     Write {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
         #[arg(long)]
-        path: String,
+        path: Option<String>,
         #[arg(long)]
         content: String,
     },
     
-    /// Delete an entity
+    /// Delete an entity (docs by --path, tests and test groups by --id)
     Delete {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
         #[arg(long)]
-        path: String,
+        path: Option<String>,
+        #[arg(long)]
+        id: Option<String>,
+        /// Also delete the tests of a test group
+        #[arg(long)]
+        cascade: bool,
+        /// Skip the confirmation prompt
+        #[arg(long, short)]
+        yes: bool,
     },
     
     /// Modify AI configuration
     ModifyAI {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
         #[arg(long)]
@@ -102,7 +151,7 @@ enum Commands {
     /// Start AI for an entity
     StartAI {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
         entity: EntityType,
     },
@@ -110,9 +159,9 @@ enum Commands {
     /// Add a step to a test
     TestAddStep {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
-        test_id: String,
+        test_id: Option<String>,
         #[arg(long)]
         command: String,
         /// Space-separated arguments for the command
@@ -123,17 +172,35 @@ enum Commands {
     /// Run a test
     TestRun {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
+        #[arg(long)]
+        test_id: Option<String>,
+        /// Re-run a failed test up to N more times, recording each attempt
+        #[arg(long, default_value = "0")]
+        retries: u32,
+        /// Run the steps on this machine with agent-browser instead of the server runner
+        #[arg(long)]
+        local: bool,
+        /// Write a JUnit XML report of the last attempt
         #[arg(long)]
-        test_id: String,
+        junit: Option<PathBuf>,
+        /// Agent tool that runs prompt steps in local runs (see /api/agents?action=tools)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Model for the agent tool
+        #[arg(long)]
+        agent_model: Option<String>,
+        /// Shell command that runs prompt steps instead of an agent tool
+        #[arg(long, conflicts_with = "agent")]
+        agent_command: Option<String>,
     },
 
     /// Mark a test as finished
     TestFinish {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
-        test_id: String,
+        test_id: Option<String>,
         #[arg(long)]
         status: String,
         #[arg(long)]
@@ -143,9 +210,9 @@ enum Commands {
     /// Get test executions
     TestExecutions {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
-        test_id: String,
+        test_id: Option<String>,
         #[arg(long, default_value = "5")]
         last: usize,
     },
@@ -153,9 +220,9 @@ enum Commands {
     /// Get test steps
     TestSteps {
         #[arg(long)]
-        repo: String,
+        repo: Option<String>,
         #[arg(long)]
-        test_id: String,
+        test_id: Option<String>,
     },
 
     /// Browser automation commands (wraps agent-browser)
@@ -165,60 +232,639 @@ enum Commands {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+
+    /// Test commands
+    #[command(visible_alias = "tests")]
+    Test {
+        #[command(subcommand)]
+        command: TestCommands,
+    },
+
+    /// Kanban board of the tasks in a full-screen terminal UI
+    Board {
+        #[arg(long)]
+        repo: Option<String>,
+    },
+
+    /// Print the shell completion script, e.g. `source <(agelum completions bash)`
+    Completions {
+        shell: commands::completions::Shell,
+    },
+
+    /// Log in to the server, or save an API key for it
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommands,
+    },
+
+    /// API keys for the server's endpoints
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommands,
+    },
+
+    /// The app's user settings (~/.agelum/user-settings.json on the server)
+    Settings {
+        #[command(subcommand)]
+        command: SettingsCommands,
+    },
+
+    /// CLI configuration (~/.agelum/cli.toml)
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Run scheduled tests (see `test schedule`) until interrupted
+    Daemon {
+        /// Shell command run when a scheduled test fails, for schedules without one
+        #[arg(long)]
+        on_failure: Option<String>,
+        /// URL that gets a POST when a scheduled test fails, for schedules without one
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Run the schedules that are due once and exit
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TestCommands {
+    /// Compare the screenshots of two executions of a test
+    Diff {
+        #[arg(long)]
+        repo: Option<String>,
+        test_id: Option<String>,
+        /// Execution to compare against, or "baseline" for the approved screenshots
+        #[arg(long, default_value = "baseline")]
+        base: String,
+        /// Execution to check, or "latest"
+        #[arg(long, default_value = "latest")]
+        head: String,
+        /// Maximum fraction of differing pixels per screenshot (0.0 - 1.0)
+        #[arg(long, default_value = "0.01")]
+        threshold: f64,
+        /// Difference metric: pixel or perceptual
+        #[arg(long, default_value = "pixel")]
+        metric: DiffMetric,
+        /// Directory for the diff images (defaults to the head execution folder)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Store the head screenshots as the approved baseline
+        #[arg(long)]
+        approve: bool,
+    },
+
+    /// Copy a test's steps and metadata into a new test
+    Clone {
+        #[arg(long)]
+        repo: Option<String>,
+        test_id: Option<String>,
+        /// Name of the new test (its folder is derived from it)
+        #[arg(long)]
+        name: String,
+        /// Group of the new test (defaults to the source test's group)
+        #[arg(long)]
+        group: Option<String>,
+    },
+
+    /// Update the YAML test specs from the server
+    Pull {
+        #[arg(long)]
+        repo: Option<String>,
+        /// Directory holding one <test-id>.yaml spec per test
+        #[arg(long, default_value = "agelum-tests")]
+        dir: PathBuf,
+        /// Overwrite local specs that changed on both sides
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Publish the YAML test specs to the server
+    Push {
+        #[arg(long)]
+        repo: Option<String>,
+        /// Directory holding one <test-id>.yaml spec per test
+        #[arg(long, default_value = "agelum-tests")]
+        dir: PathBuf,
+        /// Overwrite server tests that changed on both sides
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show which tests differ between the YAML specs and the server
+    Status {
+        #[arg(long)]
+        repo: Option<String>,
+        /// Directory holding one <test-id>.yaml spec per test
+        #[arg(long, default_value = "agelum-tests")]
+        dir: PathBuf,
+    },
+
+    /// Pass rate, duration and flakiness of tests from their execution history
+    Stats {
+        #[arg(long)]
+        repo: Option<String>,
+        /// Only include tests of this group
+        #[arg(long)]
+        group: Option<String>,
+        /// Only show tests detected as flaky
+        #[arg(long)]
+        only_flaky: bool,
+    },
+
+    /// Record steps interactively by driving the server's browser
+    Record {
+        #[arg(long)]
+        repo: Option<String>,
+        test_id: Option<String>,
+        /// AI backend for natural-language steps (defaults to the first available)
+        #[arg(long)]
+        backend: Option<String>,
+    },
+
+    /// Generate a test from a natural-language goal with the recording AI
+    Generate {
+        #[arg(long)]
+        repo: Option<String>,
+        /// Name of the new test
+        #[arg(long)]
+        name: String,
+        /// Page the test starts on
+        #[arg(long = "url")]
+        start_url: String,
+        /// What the test should do, e.g. "log in and buy the first item"
+        #[arg(long)]
+        prompt: String,
+        /// Test group of the new test
+        #[arg(long)]
+        group: Option<String>,
+        /// AI backend (defaults to the first available)
+        #[arg(long)]
+        backend: Option<String>,
+        /// Stop after this many steps
+        #[arg(long, default_value = "20")]
+        max_steps: usize,
+        /// Create the test without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Compare two executions of a test step by step
+    Compare {
+        #[arg(long)]
+        repo: Option<String>,
+        test_id: Option<String>,
+        /// Execution to compare against, or "latest"
+        #[arg(long)]
+        base: String,
+        /// Execution to check, or "latest"
+        #[arg(long, default_value = "latest")]
+        head: String,
+    },
+
+    /// Show the step-by-step log of a local run
+    Log {
+        #[arg(long)]
+        repo: Option<String>,
+        test_id: Option<String>,
+        /// Execution to show, or "latest"
+        #[arg(long, default_value = "latest")]
+        execution: String,
+    },
+
+    /// Edit the steps of a test
+    Step {
+        #[command(subcommand)]
+        command: StepCommands,
+    },
+
+    /// Run tests periodically with `agelum daemon`
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommands,
+    },
+
+    /// Dashboard of the tests with their status, to run them and read their logs
+    Ui {
+        #[arg(long)]
+        repo: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// Log in through the server's gateway (OAuth in the browser)
+    Login {
+        #[arg(long, value_enum, default_value = "github")]
+        provider: commands::auth::Provider,
+        /// Print the login URL instead of opening the browser
+        #[arg(long)]
+        no_browser: bool,
+    },
+    /// Manage the API key sent to the server
+    Token {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+    /// Show the credentials sent to the server
+    Status,
+    /// Forget the credentials saved for the server
+    Logout,
+}
+
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Save an API key (`ak_...`) for the server; read from stdin when omitted
+    Set {
+        token: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommands {
+    /// List the API keys
+    Ls,
+    /// Create an API key and print it (it can't be shown again)
+    Create {
+        #[arg(long)]
+        name: String,
+        /// Also use the key as the CLI's credentials for the server
+        #[arg(long)]
+        save: bool,
+    },
+    /// Revoke an API key
+    Revoke {
+        id: String,
+        /// Don't ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SettingsCommands {
+    /// Print the settings, or one of them (dotted path, e.g. `projects.0.url`)
+    Get {
+        key: Option<String>,
+        /// Show API keys and tokens instead of masking them
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Change a setting; the value is read as JSON when it parses, e.g. `true` or `["*"]`
+    Set {
+        key: String,
+        value: String,
+    },
+    /// Edit the settings as JSON in $EDITOR
+    Edit,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// List the profiles and check their values
+    Profiles,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// Schedule a test or a whole test group
+    Add {
+        #[arg(long)]
+        repo: Option<String>,
+        /// Test id or group name
+        target: String,
+        /// Interval, e.g. 15m, 1h or 1h 30m
+        #[arg(long)]
+        every: String,
+        /// Shell command run when a test fails; gets the failure as JSON on stdin
+        #[arg(long)]
+        on_failure: Option<String>,
+        /// URL that gets a POST with the failure as JSON when a test fails
+        #[arg(long)]
+        webhook: Option<String>,
+    },
+    /// List the schedules and their last run
+    Ls,
+    /// Remove a schedule
+    Rm {
+        id: String,
+    },
+}
+
+/// Steps are written as command line words: `open <url>`, `prompt <instruction...>`
+/// or any agent-browser command such as `click @e2`. Positions are 1-based.
+#[derive(Subcommand)]
+enum StepCommands {
+    /// Insert a step at a position
+    Insert {
+        #[arg(long)]
+        repo: Option<String>,
+        #[arg(long)]
+        test_id: Option<String>,
+        #[arg(long)]
+        at: usize,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        step: Vec<String>,
+    },
+
+    /// Remove a step
+    Rm {
+        #[arg(long)]
+        repo: Option<String>,
+        #[arg(long)]
+        test_id: Option<String>,
+        index: usize,
+    },
+
+    /// Move a step to another position
+    Mv {
+        #[arg(long)]
+        repo: Option<String>,
+        #[arg(long)]
+        test_id: Option<String>,
+        from: usize,
+        to: usize,
+    },
+
+    /// Replace a step
+    Set {
+        #[arg(long)]
+        repo: Option<String>,
+        #[arg(long)]
+        test_id: Option<String>,
+        index: usize,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        step: Vec<String>,
+    },
+
+    /// Edit the step list in $EDITOR as YAML (or JSON)
+    Edit {
+        #[arg(long)]
+        repo: Option<String>,
+        test_id: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Whether the command calls the configured server, so that checking the server
+/// on startup is worth its time. `status` and `auth` report on the server
+/// themselves, and the daemon calls the servers of its schedules.
+fn calls_server(command: &Commands) -> bool {
+    match command {
+        Commands::Status
+        | Commands::Completions { .. }
+        | Commands::Auth { .. }
+        | Commands::Config { .. }
+        | Commands::Daemon { .. } => false,
+        // Everything but `navigate` goes straight to agent-browser
+        Commands::Browser { args } => args.first().is_some_and(|arg| arg == "navigate"),
+        Commands::Test { command: TestCommands::Schedule { command } } => {
+            matches!(command, ScheduleCommands::Add { .. })
+        }
+        _ => true,
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    clap_complete::CompleteEnv::with_factory(|| completion::with_completers(Cli::command()))
+        .var(completion::ENV_VAR)
+        .complete();
     let cli = Cli::parse();
-    let client = reqwest::Client::new();
+    // Checking the profiles has to work when the selected one is broken
+    if let Commands::Config { command: ConfigCommands::Profiles } = &cli.command {
+        return commands::config::profiles(cli.profile.as_deref());
+    }
+    let mut settings = config::Settings::resolve(cli.profile.as_deref(), cli.url)?;
+    if let Some(output) = cli.output {
+        settings.output = output;
+    }
+    if let Some(timeout) = &cli.connect_timeout {
+        settings.connect_timeout = config::parse_timeout(timeout)
+            .map_err(|e| anyhow::anyhow!("Invalid --connect-timeout {:?}: {}", timeout, e))?;
+    }
+    if let Some(timeout) = &cli.timeout {
+        settings.timeout =
+            config::parse_timeout(timeout).map_err(|e| anyhow::anyhow!("Invalid --timeout {:?}: {}", timeout, e))?;
+    }
+    settings.verbose |= cli.verbose;
+    let settings = settings.install();
+    let client = settings.client()?;
+    if calls_server(&cli.command) {
+        compat::check(&client, settings).await;
+    }
 
     match cli.command {
+        Commands::Status => {
+            commands::status::execute(&client, settings).await?;
+        }
         Commands::ListRepos => {
-            commands::list_repos::execute(&client, &cli.url).await?;
+            commands::list_repos::execute(&client, &settings.url, settings.output).await?;
         }
         Commands::List { repo, entity } => {
-            commands::list::execute(&client, &cli.url, &repo, entity).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::list::execute(&client, &settings.url, &repo, entity, settings.output).await?;
         }
         Commands::Create { repo, entity, title, description, state } => {
-            commands::create::execute(&client, &cli.url, &repo, entity, &title, description, state).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::create::execute(&client, &settings.url, &repo, entity, &title, description, state).await?;
         }
         Commands::Move { repo, entity, id, from_state, to_state } => {
-            commands::r#move::execute(&client, &cli.url, &repo, entity, &id, &from_state, &to_state).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::r#move::execute(&client, &settings.url, &repo, entity, id, &from_state, &to_state).await?;
         }
         Commands::Read { repo, entity, path } => {
-            commands::read::execute(&client, &cli.url, &repo, entity, &path).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::read::execute(&client, &settings.url, &repo, entity, path).await?;
         }
         Commands::Write { repo, entity, path, content } => {
-            commands::write::execute(&client, &cli.url, &repo, entity, &path, &content).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::write::execute(&client, &settings.url, &repo, entity, path, &content).await?;
         }
-        Commands::Delete { repo, entity, path } => {
-            commands::delete::execute(&client, &cli.url, &repo, entity, &path).await?;
+        Commands::Delete { repo, entity, path, id, cascade, yes } => {
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::delete::execute(&client, &settings.url, &repo, entity, path, id, cascade, yes).await?;
         }
         Commands::ModifyAI { repo, entity, config } => {
-            commands::modify_ai::execute(&client, &cli.url, &repo, entity, &config).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::modify_ai::execute(&client, &settings.url, &repo, entity, &config).await?;
         }
         Commands::StartAI { repo, entity } => {
-            commands::start_ai::execute(&client, &cli.url, &repo, entity).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::start_ai::execute(&client, &settings.url, &repo, entity).await?;
         }
         Commands::TestAddStep { repo, test_id, command, args } => {
-            commands::test_add_step::execute(&client, &cli.url, &repo, &test_id, &command, args).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+            commands::test_add_step::execute(&client, &settings.url, &repo, &test_id, &command, args).await?;
         }
-        Commands::TestRun { repo, test_id } => {
-            commands::test_run::execute(&client, &cli.url, &repo, &test_id).await?;
+        Commands::TestRun { repo, test_id, retries, local, junit, agent, agent_model, agent_command } => {
+            let repo = picker::repo(&client, settings, repo).await?;
+            let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+            let agent = agent::AgentConfig { tool: agent, model: agent_model, command: agent_command };
+            commands::test_run::execute(&client, &settings.url, &repo, &test_id, retries, local, junit.as_deref(), agent)
+                .await?;
         }
         Commands::TestFinish { repo, test_id, status, error } => {
-            commands::test_finish::execute(&client, &cli.url, &repo, &test_id, &status, error).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+            commands::test_finish::execute(&client, &settings.url, &repo, &test_id, &status, error).await?;
         }
         Commands::TestExecutions { repo, test_id, last } => {
-            commands::test_executions::execute(&client, &cli.url, &repo, &test_id, last).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+            commands::test_executions::execute(&client, &settings.url, &repo, &test_id, last).await?;
         }
         Commands::TestSteps { repo, test_id } => {
-            commands::test_steps::execute(&client, &cli.url, &repo, &test_id).await?;
+            let repo = picker::repo(&client, settings, repo).await?;
+            let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+            commands::test_steps::execute(&client, &settings.url, &repo, &test_id).await?;
         }
         Commands::Browser { args } => {
-            commands::browser::execute(&client, &cli.url, args).await?;
+            commands::browser::execute(&client, &settings.url, args).await?;
+        }
+        Commands::Test { command } => match command {
+            TestCommands::Diff { repo, test_id, base, head, threshold, metric, out, approve } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                let test_id = picker::test(&client, &settings.url, &repo, test_id, "<TEST_ID>").await?;
+                commands::test_diff::execute(
+                    &client, &settings.url, &repo, &test_id, &base, &head, threshold, metric, out, approve,
+                )
+                .await?;
+            }
+            TestCommands::Clone { repo, test_id, name, group } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                let test_id = picker::test(&client, &settings.url, &repo, test_id, "<TEST_ID>").await?;
+                commands::test_clone::execute(&client, &settings.url, &repo, &test_id, &name, group).await?;
+            }
+            TestCommands::Pull { repo, dir, force } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                commands::test_sync::pull(&client, &settings.url, &repo, &dir, force).await?;
+            }
+            TestCommands::Push { repo, dir, force } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                commands::test_sync::push(&client, &settings.url, &repo, &dir, force).await?;
+            }
+            TestCommands::Status { repo, dir } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                commands::test_sync::status(&client, &settings.url, &repo, &dir).await?;
+            }
+            TestCommands::Stats { repo, group, only_flaky } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                commands::test_stats::execute(&client, &settings.url, &repo, group, only_flaky).await?;
+            }
+            TestCommands::Record { repo, test_id, backend } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                let test_id = picker::test(&client, &settings.url, &repo, test_id, "<TEST_ID>").await?;
+                commands::test_record::execute(&client, &settings.url, &repo, &test_id, backend).await?;
+            }
+            TestCommands::Generate { repo, name, start_url, prompt, group, backend, max_steps, yes } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                commands::test_generate::execute(
+                    &client, &settings.url, &repo, &name, &start_url, &prompt, group, backend, max_steps, yes,
+                )
+                .await?;
+            }
+            TestCommands::Compare { repo, test_id, base, head } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                let test_id = picker::test(&client, &settings.url, &repo, test_id, "<TEST_ID>").await?;
+                commands::test_compare::execute(&client, &settings.url, &repo, &test_id, &base, &head).await?;
+            }
+            TestCommands::Log { repo, test_id, execution } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                let test_id = picker::test(&client, &settings.url, &repo, test_id, "<TEST_ID>").await?;
+                commands::test_log::execute(&client, &settings.url, &repo, &test_id, &execution).await?;
+            }
+            TestCommands::Schedule { command } => match command {
+                ScheduleCommands::Add { repo, target, every, on_failure, webhook } => {
+                    let repo = picker::repo(&client, settings, repo).await?;
+                    commands::test_schedule::add(&client, &settings.url, &repo, &target, &every, on_failure, webhook)
+                        .await?;
+                }
+                ScheduleCommands::Ls => commands::test_schedule::list()?,
+                ScheduleCommands::Rm { id } => commands::test_schedule::remove(&id)?,
+            },
+            TestCommands::Ui { repo } => {
+                let repo = picker::repo(&client, settings, repo).await?;
+                commands::test_ui::execute(&client, &settings.url, &repo).await?;
+            }
+            TestCommands::Step { command } => match command {
+                StepCommands::Insert { repo, test_id, at, step } => {
+                    let repo = picker::repo(&client, settings, repo).await?;
+                    let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+                    commands::test_step::insert(&client, &settings.url, &repo, &test_id, at, step).await?;
+                }
+                StepCommands::Rm { repo, test_id, index } => {
+                    let repo = picker::repo(&client, settings, repo).await?;
+                    let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+                    commands::test_step::remove(&client, &settings.url, &repo, &test_id, index).await?;
+                }
+                StepCommands::Mv { repo, test_id, from, to } => {
+                    let repo = picker::repo(&client, settings, repo).await?;
+                    let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+                    commands::test_step::move_step(&client, &settings.url, &repo, &test_id, from, to).await?;
+                }
+                StepCommands::Set { repo, test_id, index, step } => {
+                    let repo = picker::repo(&client, settings, repo).await?;
+                    let test_id = picker::test(&client, &settings.url, &repo, test_id, "--test-id").await?;
+                    commands::test_step::set(&client, &settings.url, &repo, &test_id, index, step).await?;
+                }
+                StepCommands::Edit { repo, test_id, json } => {
+                    let repo = picker::repo(&client, settings, repo).await?;
+                    let test_id = picker::test(&client, &settings.url, &repo, test_id, "<TEST_ID>").await?;
+                    commands::test_step::edit(&client, &settings.url, &repo, &test_id, json).await?;
+                }
+            },
+        },
+        Commands::Board { repo } => {
+            let repo = picker::repo(&client, settings, repo).await?;
+            commands::board::execute(&client, &settings.url, &repo).await?;
+        }
+        Commands::Completions { shell } => commands::completions::execute(shell)?,
+        Commands::Auth { command } => match command {
+            AuthCommands::Login { provider, no_browser } => commands::auth::login(settings, provider, !no_browser).await?,
+            AuthCommands::Token { command: TokenCommands::Set { token } } => commands::auth::set_token(settings, token)?,
+            AuthCommands::Status => commands::auth::status(&client, settings).await?,
+            AuthCommands::Logout => commands::auth::logout(settings)?,
+        },
+        Commands::Keys { command } => match command {
+            KeysCommands::Ls => commands::keys::list(&client, settings).await?,
+            KeysCommands::Create { name, save } => commands::keys::create(&client, settings, &name, save).await?,
+            KeysCommands::Revoke { id, yes } => commands::keys::revoke(&client, settings, &id, yes).await?,
+        },
+        Commands::Settings { command } => match command {
+            SettingsCommands::Get { key, reveal } => {
+                commands::settings::get(&client, settings, key.as_deref(), reveal).await?
+            }
+            SettingsCommands::Set { key, value } => commands::settings::set(&client, settings, &key, &value).await?,
+            SettingsCommands::Edit => commands::settings::edit(&client, settings).await?,
+        },
+        Commands::Config { .. } => unreachable!("handled before the settings are resolved"),
+        Commands::Daemon { on_failure, webhook, once } => {
+            commands::daemon::execute(&client, settings, on_failure, webhook, once).await?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn tests_is_an_alias_of_test() {
+        for name in ["test", "tests"] {
+            let cli = Cli::try_parse_from(["agelum", name, "ui", "--repo", "demo"]).unwrap();
+            assert!(matches!(cli.command, Commands::Test { command: TestCommands::Ui { repo: Some(ref repo) } } if repo == "demo"));
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::api;
+use crate::config::{self, Settings};
+use crate::interactive;
+use crate::types::{FileNode, Repository};
+use std::path::{Path, PathBuf};
+
+// Values left out on the command line are picked from a fuzzy-searchable list
+// of candidates fetched from the server, when the session is interactive.
+
+/// The given repository, else `AGELUM_REPO`, else the one checked out in the
+/// working directory, else the profile's default one, else a picked one.
+pub async fn repo(client: &reqwest::Client, settings: &Settings, given: Option<String>) -> anyhow::Result<String> {
+    if let Some(repo) = given {
+        return Ok(repo);
+    }
+    // Set for this shell or CI job, so it wins over the checkout
+    if settings.repo_from_env
+        && let Some(repo) = settings.repo.clone()
+    {
+        return Ok(repo);
+    }
+    let mut repositories = None;
+    let mut unmatched = false;
+    if let Some(root) = std::env::current_dir().ok().and_then(|dir| checkout_root(&dir)) {
+        match api::fetch_repositories(client, &settings.url).await {
+            Ok(fetched) => {
+                if let Some(repository) = find_checkout(&root, &fetched) {
+                    return Ok(repository.name.clone());
+                }
+                eprintln!("⚠ {} is not one of the repositories of {}", root.display(), settings.url);
+                repositories = Some(fetched);
+            }
+            // The configured repository may do without the list
+            Err(e) if settings.repo.is_some() => {
+                eprintln!("⚠ Could not tell which repository {} is: {}", root.display(), e);
+            }
+            Err(e) => return Err(e),
+        }
+        unmatched = true;
+    }
+    if let Some(repo) = settings.repo.clone() {
+        if unmatched {
+            eprintln!("  Using {} from the configuration", repo);
+        }
+        return Ok(repo);
+    }
+    interactive::require("--repo")?;
+    let repositories = match repositories {
+        Some(repositories) => repositories,
+        None => api::fetch_repositories(client, &settings.url).await?,
+    };
+    let items = repositories
+        .iter()
+        .map(|r| (r.name.clone(), format!("{}  {}", r.name, r.path)))
+        .collect();
+    pick("Repository", "repositories", items)
+}
+
+pub async fn task(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    given: Option<String>,
+    flag: &str,
+) -> anyhow::Result<String> {
+    if let Some(id) = given {
+        return Ok(id);
+    }
+    interactive::require(flag)?;
+    let tasks = api::fetch_tasks(client, url, repo).await?;
+    let items = tasks
+        .iter()
+        .map(|t| (t.id.clone(), format!("{}  {}  [{}]", t.id, t.title, t.state)))
+        .collect();
+    pick("Task", "tasks", items)
+}
+
+pub async fn test(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    given: Option<String>,
+    flag: &str,
+) -> anyhow::Result<String> {
+    if let Some(id) = given {
+        return Ok(id);
+    }
+    interactive::require(flag)?;
+    let tests = api::fetch_tests(client, url, repo).await?;
+    let items = tests
+        .iter()
+        .map(|t| {
+            let group = t.group.as_deref().unwrap_or("-");
+            (t.id.clone(), format!("{} / {}  ({})", group, t.name, t.id))
+        })
+        .collect();
+    pick("Test", "tests", items)
+}
+
+pub async fn test_group(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    given: Option<String>,
+    flag: &str,
+) -> anyhow::Result<String> {
+    if let Some(name) = given {
+        return Ok(name);
+    }
+    interactive::require(flag)?;
+    let groups = api::fetch_test_groups(client, url, repo).await?;
+    let items = groups.into_iter().map(|g| (g.clone(), g)).collect();
+    pick("Test group", "test groups", items)
+}
+
+/// Picks a markdown file under the repository's `.agelum/doc`; returns its path
+/// as the file endpoints expect it.
+pub async fn doc(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    given: Option<String>,
+    flag: &str,
+) -> anyhow::Result<String> {
+    if let Some(path) = given {
+        return Ok(path);
+    }
+    interactive::require(flag)?;
+    let files = api::fetch_files(client, url, repo, "doc").await?;
+    let mut paths = Vec::new();
+    if let Some(tree) = &files.tree {
+        collect_docs(tree, &mut paths);
+    }
+    let root = format!("{}/", files.root_path.trim_end_matches('/'));
+    let items = paths
+        .into_iter()
+        .map(|path| {
+            let label = path.strip_prefix(&root).unwrap_or(&path).to_string();
+            (path, label)
+        })
+        .collect();
+    pick("Doc", "docs", items)
+}
+
+fn collect_docs(node: &FileNode, paths: &mut Vec<String>) {
+    if node.kind == "file" && node.path.ends_with(".md") {
+        paths.push(node.path.clone());
+    }
+    for child in &node.children {
+        collect_docs(child, paths);
+    }
+}
+
+/// Nearest directory, from `dir` up, holding a `.agelum` folder; the CLI's own
+/// `~/.agelum` doesn't count.
+pub fn checkout_root(dir: &Path) -> Option<PathBuf> {
+    let home = config::agelum_home().ok().and_then(|h| h.canonicalize().ok());
+    dir.ancestors()
+        .find(|d| {
+            let marker = d.join(".agelum");
+            marker.is_dir() && marker.canonicalize().ok() != home
+        })
+        .map(Path::to_path_buf)
+}
+
+/// The repository checked out at `root`: the one at that path (as seen from
+/// this machine, see `local_path`), else the only one named like the folder,
+/// for servers whose paths don't exist here.
+pub fn find_checkout<'a>(root: &Path, repositories: &'a [Repository]) -> Option<&'a Repository> {
+    let root = root.canonicalize().ok()?;
+    if let Some(repository) = repositories
+        .iter()
+        .find(|r| Path::new(&r.path).canonicalize().is_ok_and(|p| p == root))
+    {
+        return Some(repository);
+    }
+    let name = root.file_name()?.to_str()?;
+    let mut named = repositories.iter().filter(|r| r.name == name);
+    match (named.next(), named.next()) {
+        (Some(repository), None) => Some(repository),
+        _ => None,
+    }
+}
+
+/// Shows the labels and returns the value of the chosen one.
+fn pick(prompt: &str, what: &str, items: Vec<(String, String)>) -> anyhow::Result<String> {
+    if items.is_empty() {
+        anyhow::bail!("No {} to choose from", what);
+    }
+    let labels: Vec<&str> = items.iter().map(|(_, label)| label.as_str()).collect();
+    let index = interactive::fuzzy_select(prompt, &labels)?;
+    Ok(items[index].0.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository(name: &str, path: &Path) -> Repository {
+        Repository { name: name.to_string(), path: path.to_string_lossy().into_owned(), tests_dir: None }
+    }
+
+    #[test]
+    fn finds_the_repository_at_the_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkout = dir.path().join("web");
+        std::fs::create_dir_all(&checkout).unwrap();
+        let repositories = vec![repository("api", &dir.path().join("api")), repository("shop", &checkout)];
+
+        // By path, whatever the folder is named
+        assert_eq!(find_checkout(&checkout, &repositories).map(|r| r.name.as_str()), Some("shop"));
+        // Through another spelling of the same path
+        let dotted = checkout.join("..").join("web");
+        assert_eq!(find_checkout(&dotted, &repositories).map(|r| r.name.as_str()), Some("shop"));
+    }
+
+    #[test]
+    fn falls_back_to_the_folder_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkout = dir.path().join("shop");
+        std::fs::create_dir_all(&checkout).unwrap();
+        // Paths of the server's machine, which don't exist here
+        let remote = Path::new("/srv/agelum/shop");
+
+        let repositories = vec![repository("shop", remote), repository("api", Path::new("/srv/agelum/api"))];
+        assert_eq!(find_checkout(&checkout, &repositories).map(|r| r.name.as_str()), Some("shop"));
+
+        let ambiguous = vec![repository("shop", remote), repository("shop", Path::new("/srv/other/shop"))];
+        assert!(find_checkout(&checkout, &ambiguous).is_none());
+
+        let unrelated = vec![repository("api", Path::new("/srv/agelum/api"))];
+        assert!(find_checkout(&checkout, &unrelated).is_none());
+    }
+}
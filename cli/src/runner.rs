@@ -0,0 +1,421 @@
+use crate::agent::{self, Agent, AgentConfig};
+use crate::agent_browser::{Browser, Snapshot};
+use crate::types::{Assertion, StepAction, TestStep};
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+// Runs test steps on this machine through agent-browser, for `test-run --local`.
+
+/// How long an assertion keeps polling when the step has no `timeout`.
+pub const DEFAULT_ASSERT_TIMEOUT_MS: u64 = 5000;
+const ASSERT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lifecycle hooks and timeouts of a test or group, in `run.json` next to the
+/// test's `test.json` or in the group folder.
+pub const RUN_CONFIG_FILE: &str = "run.json";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RunConfig {
+    pub before_all: Vec<Hook>,
+    pub before_each: Vec<Hook>,
+    pub after_each: Vec<Hook>,
+    pub after_all: Vec<Hook>,
+    /// Milliseconds allowed for the whole test (per data row)
+    pub timeout: Option<u64>,
+    /// Milliseconds allowed for each step or hook
+    pub step_timeout: Option<u64>,
+    /// Agent that runs prompt steps
+    pub agent: AgentConfig,
+}
+
+/// A hook is a test step or a shell command: `{"shell": "npm run db:seed"}`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Hook {
+    Shell { shell: String },
+    Step(TestStep),
+}
+
+impl std::fmt::Display for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hook::Shell { shell } => write!(f, "$ {}", shell),
+            Hook::Step(step) => write!(f, "{}", step),
+        }
+    }
+}
+
+/// Error of a step or hook that ran past its timeout. The process it was
+/// waiting on is killed.
+#[derive(Debug)]
+pub struct TimedOut(pub Duration);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out after {}ms", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Reads the run config in a folder; missing files are an empty config.
+pub fn read_run_config(dir: &Path) -> anyhow::Result<RunConfig> {
+    let path = dir.join(RUN_CONFIG_FILE);
+    if !path.exists() {
+        return Ok(RunConfig::default());
+    }
+    serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+/// Runs a future, failing with `TimedOut` once the timeout expires. Dropping the
+/// future kills any agent-browser or shell process it started.
+pub async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    future: impl std::future::Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .map_err(|_| anyhow::Error::new(TimedOut(timeout)))?,
+        None => future.await,
+    }
+}
+
+/// What a step printed. `exit_code` is only set for steps that ran a single
+/// process.
+#[derive(Debug, Default)]
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+}
+
+impl Output {
+    fn message(stdout: String) -> Self {
+        Output { stdout, ..Default::default() }
+    }
+}
+
+/// Error of a process that exited with a failure status, or reported a failure
+/// in its output. Keeps its output so it can be recorded in the step log.
+#[derive(Debug)]
+pub struct CommandFailed {
+    pub command: String,
+    /// Failure reported by the command itself, e.g. an agent-browser `--json` error
+    pub reason: Option<String>,
+    pub output: Output,
+}
+
+impl std::fmt::Display for CommandFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stderr = self.output.stderr.trim();
+        let message = match &self.reason {
+            Some(reason) => reason.as_str(),
+            None if stderr.is_empty() => self.output.stdout.trim(),
+            None => stderr,
+        };
+        match self.output.exit_code {
+            Some(0) => write!(f, "{} failed: {}", self.command, message),
+            Some(code) => write!(f, "{} failed (exit status {}): {}", self.command, code, message),
+            None => write!(f, "{} failed (killed by a signal): {}", self.command, message),
+        }
+    }
+}
+
+impl std::error::Error for CommandFailed {}
+
+/// Outcome of evaluating an assertion once.
+struct Check {
+    passed: bool,
+    expected: String,
+    actual: String,
+}
+
+/// Runs one step and returns its output. Prompt steps need an agent and fail
+/// without one, so they are never counted as checked.
+pub async fn run_step(step: &TestStep, browser: &Browser, agent: Option<&Agent>) -> anyhow::Result<Output> {
+    match &step.action {
+        StepAction::Prompt { instruction } => match agent {
+            Some(agent) => agent::run_prompt(agent, browser, instruction).await,
+            None => anyhow::bail!(
+                "No agent configured for prompt steps; choose one with --agent or AGELUM_AGENT"
+            ),
+        },
+        StepAction::Assert { assertion } => {
+            check_assertion(browser, assertion, step.timeout).await.map(Output::message)
+        }
+        StepAction::Other(fields) => anyhow::bail!(
+            "'{}' steps only run on the server; run the test without --local",
+            fields.get("action").and_then(|a| a.as_str()).unwrap_or_default()
+        ),
+        _ => browser.run(&step.browser_args().unwrap_or_default()).await,
+    }
+}
+
+/// Runs a shell command with `sh -c` and returns its output.
+pub async fn run_shell(command: &str) -> anyhow::Result<Output> {
+    let mut process = Command::new("sh");
+    process.args(["-c", command]);
+    capture(process, command, None)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", command, e))?
+}
+
+/// Polls the page until the assertion holds or the timeout expires.
+pub async fn check_assertion(
+    browser: &Browser,
+    assertion: &Assertion,
+    timeout: Option<u64>,
+) -> anyhow::Result<String> {
+    // A selector that can't match anything fails now, not after the timeout
+    if let Assertion::Element { selector, .. } = assertion {
+        Selector::parse(selector)?;
+    }
+    let timeout = Duration::from_millis(timeout.unwrap_or(DEFAULT_ASSERT_TIMEOUT_MS));
+    let started = Instant::now();
+    loop {
+        // agent-browser errors (e.g. no page yet) are retried like a failed check
+        let result = evaluate(browser, assertion).await;
+        match &result {
+            Ok(check) if check.passed => return Ok(format!("✓ {}", check.actual)),
+            _ if started.elapsed() >= timeout => {
+                let check = result?;
+                anyhow::bail!(
+                    "Assertion failed after {}ms\n  expected: {}\n  actual:   {}",
+                    timeout.as_millis(),
+                    check.expected,
+                    check.actual
+                );
+            }
+            _ => tokio::time::sleep(ASSERT_POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Reads what the assertion looks at from the page and checks it once.
+async fn evaluate(browser: &Browser, assertion: &Assertion) -> anyhow::Result<Check> {
+    match assertion {
+        Assertion::Text { text } => Ok(judge_text(&browser.snapshot().await?.text, text)),
+        Assertion::Element { selector, count } => judge_element(&browser.snapshot().await?, selector, *count),
+        Assertion::Url { pattern } => judge_url(&browser.url().await?, pattern),
+        Assertion::Title { title } => Ok(judge_title(&browser.title().await?, title)),
+    }
+}
+
+fn judge_text(snapshot: &str, text: &str) -> Check {
+    let passed = snapshot.contains(text);
+    Check {
+        passed,
+        expected: format!("text {:?} visible", text),
+        actual: if passed {
+            format!("text {:?} visible", text)
+        } else {
+            format!("not in the page snapshot ({} lines)", snapshot.lines().count())
+        },
+    }
+}
+
+fn judge_element(snapshot: &Snapshot, selector: &str, count: Option<usize>) -> anyhow::Result<Check> {
+    let found = count_elements(snapshot, &Selector::parse(selector)?);
+    Ok(Check {
+        passed: match count {
+            Some(count) => found == count,
+            None => found > 0,
+        },
+        expected: match count {
+            Some(count) => format!("{} elements matching {}", count, selector),
+            None => format!("an element matching {}", selector),
+        },
+        actual: format!("{} elements matching {}", found, selector),
+    })
+}
+
+fn judge_url(url: &str, pattern: &str) -> anyhow::Result<Check> {
+    let url = url.trim();
+    Ok(Check {
+        passed: regex::Regex::new(pattern)?.is_match(url),
+        expected: format!("URL matching /{}/", pattern),
+        actual: format!("URL {:?}", url),
+    })
+}
+
+fn judge_title(actual: &str, title: &str) -> Check {
+    let actual = actual.trim();
+    Check {
+        passed: actual == title,
+        expected: format!("title {:?}", title),
+        actual: format!("title {:?}", actual),
+    }
+}
+
+/// Element selector of an `assert element` step. Elements are matched in the
+/// page snapshot, which has no CSS, so only refs, roles and names are understood.
+#[derive(Debug, PartialEq)]
+pub enum Selector {
+    /// `@e2`
+    Ref(String),
+    /// `button` or `Login`: the role or the name
+    Word(String),
+    /// `button "Sign in"`
+    RoleName { role: String, name: String },
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> anyhow::Result<Self> {
+        let unsupported = || {
+            anyhow::anyhow!(
+                "Unsupported element selector '{}': use @ref, a role or name, or role \"name\" \
+                 as shown in the page snapshot (CSS selectors are not supported)",
+                selector
+            )
+        };
+        let is_word = |word: &str| {
+            !word.is_empty() && word.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        };
+
+        let words = shell_words::split(selector).map_err(|_| unsupported())?;
+        match words.as_slice() {
+            [reference] if reference.starts_with('@') && is_word(&reference[1..]) => {
+                Ok(Selector::Ref(reference[1..].to_string()))
+            }
+            [word] if is_word(word) => Ok(Selector::Word(word.clone())),
+            [role, name @ ..] if is_word(role) && !name.is_empty() => Ok(Selector::RoleName {
+                role: role.clone(),
+                name: name.join(" "),
+            }),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+/// Counts snapshot elements matching a selector.
+fn count_elements(snapshot: &Snapshot, selector: &Selector) -> usize {
+    snapshot
+        .elements()
+        .iter()
+        .filter(|(reference, element)| match selector {
+            Selector::Ref(r) => *reference == r,
+            Selector::Word(word) => &element.role == word || element.name.as_ref() == Some(word),
+            Selector::RoleName { role, name } => &element.role == role && element.name.as_ref() == Some(name),
+        })
+        .count()
+}
+
+/// Runs a process to completion, capturing its output and writing `input` to its
+/// stdin. The outer error is a failure to start it, the inner one a failure exit
+/// status.
+pub async fn capture(
+    mut process: Command,
+    command: &str,
+    input: Option<&str>,
+) -> std::io::Result<anyhow::Result<Output>> {
+    process
+        .kill_on_drop(true)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = process.spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // A command that doesn't read its input closes the pipe early
+        let _ = stdin.write_all(input.as_bytes()).await;
+    }
+    let output = child.wait_with_output().await?;
+    let captured = Output {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code(),
+    };
+    if !output.status.success() {
+        return Ok(Err(anyhow::Error::new(CommandFailed {
+            command: command.to_string(),
+            reason: None,
+            output: captured,
+        })));
+    }
+    Ok(Ok(captured))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn prompt_steps_fail_without_an_agent() {
+        let step = TestStep::from_args(&["prompt".to_string(), "Check the banner".to_string()]).unwrap();
+        let e = run_step(&step, &Browser::default(), None).await.unwrap_err();
+        assert!(e.to_string().contains("No agent configured"), "{}", e);
+    }
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            text: [
+                "- heading \"Invoices\" [ref=e1]",
+                "- button \"Sign in\" [ref=e2]",
+                "- button \"Cancel\" [ref=e3]",
+                "- link \"Sign in\" [ref=e4]",
+                "- textbox [ref=e5]",
+            ]
+            .join("\n"),
+            refs: Default::default(),
+        }
+    }
+
+    #[test]
+    fn parses_element_selectors() {
+        assert_eq!(Selector::parse("@e2").unwrap(), Selector::Ref("e2".to_string()));
+        assert_eq!(Selector::parse("button").unwrap(), Selector::Word("button".to_string()));
+        assert_eq!(Selector::parse(r#"button "Sign in""#).unwrap(), Selector::RoleName {
+            role: "button".to_string(),
+            name: "Sign in".to_string(),
+        });
+        for css in [".row > td", "#email", "div.item", "input[name=q]", "@", "'unclosed", ""] {
+            let e = Selector::parse(css).unwrap_err();
+            assert!(e.to_string().contains("Unsupported element selector"), "{}: {}", css, e);
+        }
+    }
+
+    #[test]
+    fn counts_matching_elements() {
+        let count = |selector| count_elements(&snapshot(), &Selector::parse(selector).unwrap());
+        assert_eq!(count("@e3"), 1);
+        assert_eq!(count("@e9"), 0);
+        assert_eq!(count("button"), 2);
+        assert_eq!(count("Cancel"), 1);
+        assert_eq!(count(r#"button "Sign in""#), 1);
+        assert_eq!(count(r#"link "Sign in""#), 1);
+        assert_eq!(count(r#"link Cancel"#), 0);
+    }
+
+    #[test]
+    fn judges_assertions() {
+        let text = judge_text(&snapshot().text, "Invoices");
+        assert!(text.passed);
+        let missing = judge_text(&snapshot().text, "Welcome");
+        assert!(!missing.passed);
+        assert_eq!(missing.actual, "not in the page snapshot (5 lines)");
+
+        let element = judge_element(&snapshot(), "button", Some(2)).unwrap();
+        assert!(element.passed);
+        let element = judge_element(&snapshot(), "button", Some(1)).unwrap();
+        assert!(!element.passed);
+        assert_eq!((element.expected.as_str(), element.actual.as_str()), (
+            "1 elements matching button",
+            "2 elements matching button"
+        ));
+        assert!(judge_element(&snapshot(), ".row > td", None).is_err());
+
+        assert!(judge_url("https://example.com/home\n", "/home$").unwrap().passed);
+        assert!(!judge_url("https://example.com/login", "/home$").unwrap().passed);
+        assert!(judge_url("https://example.com/", "(").is_err());
+
+        assert!(judge_title(" Dashboard\n", "Dashboard").passed);
+        let title = judge_title("Login", "Dashboard");
+        assert_eq!((title.passed, title.actual.as_str()), (false, "title \"Login\""));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::config;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Scheduled test runs, kept in `~/.agelum/schedule.json` together with the state
+// the daemon needs to resume after a restart.
+
+pub const SCHEDULE_FILE: &str = "schedule.json";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ScheduleStore {
+    pub schedules: Vec<Schedule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Test,
+    Group,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub id: String,
+    /// Server the schedule was added on
+    pub url: String,
+    pub repo: String,
+    /// Test id or group name
+    pub target: String,
+    pub kind: TargetKind,
+    /// Interval such as `15m` or `1h 30m`
+    pub every: String,
+    /// Shell command run when a scheduled test fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<String>,
+    /// URL that gets a POST when a scheduled test fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// When the last run started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl Schedule {
+    pub fn interval(&self) -> anyhow::Result<Duration> {
+        parse_interval(&self.every)
+    }
+
+    /// When the schedule is due next: one interval after the last run started.
+    /// `None` when it never ran, meaning now.
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        let interval = chrono::Duration::from_std(self.interval().ok()?).ok()?;
+        Some(self.last_run? + interval)
+    }
+
+    /// Runs missed while the daemon was stopped are due once, not once per
+    /// missed interval.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_run().is_none_or(|next| next <= now)
+    }
+}
+
+/// Parses an interval such as `15m`, failing on zero.
+pub fn parse_interval(every: &str) -> anyhow::Result<Duration> {
+    let interval = humantime::parse_duration(every)
+        .map_err(|e| anyhow::anyhow!("Invalid interval {:?}: {}", every, e))?;
+    if interval.is_zero() {
+        anyhow::bail!("The interval must be longer than zero");
+    }
+    Ok(interval)
+}
+
+pub fn schedule_path() -> anyhow::Result<PathBuf> {
+    Ok(config::agelum_home()?.join(SCHEDULE_FILE))
+}
+
+impl ScheduleStore {
+    /// Reads the schedules; a missing file has none.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(ScheduleStore::default());
+        }
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Writes the schedules through a temporary file, so a daemon killed while
+    /// saving doesn't leave a truncated file behind.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Next free id: `s1`, `s2`, ...
+    pub fn next_id(&self) -> String {
+        let max = self
+            .schedules
+            .iter()
+            .filter_map(|s| s.id.strip_prefix('s')?.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0);
+        format!("s{}", max + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(every: &str, last_run: Option<DateTime<Utc>>) -> Schedule {
+        Schedule {
+            id: "s1".to_string(),
+            url: config::DEFAULT_URL.to_string(),
+            repo: "demo".to_string(),
+            target: "t1".to_string(),
+            kind: TargetKind::Test,
+            every: every.to_string(),
+            on_failure: None,
+            webhook: None,
+            last_run,
+            last_status: None,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_interval("15m").unwrap(), Duration::from_secs(15 * 60));
+        assert_eq!(parse_interval("1h 30m").unwrap(), Duration::from_secs(90 * 60));
+        assert!(parse_interval("0s").is_err());
+        assert!(parse_interval("often").is_err());
+        assert!(parse_interval("").is_err());
+    }
+
+    #[test]
+    fn is_due_an_interval_after_the_last_run() {
+        let now = Utc::now();
+        let minutes = |m| chrono::Duration::minutes(m);
+
+        assert!(schedule("15m", None).is_due(now));
+        assert!(!schedule("15m", Some(now - minutes(10))).is_due(now));
+        assert!(schedule("15m", Some(now - minutes(15))).is_due(now));
+        // Missed runs make it due once, right away
+        let missed = schedule("15m", Some(now - minutes(24 * 60)));
+        assert!(missed.is_due(now));
+        assert_eq!(missed.next_run(), Some(now - minutes(24 * 60) + minutes(15)));
+    }
+}
@@ -0,0 +1,170 @@
+use crossterm::event::{self, KeyEvent};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::DefaultTerminal;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Shared pieces of the full-screen terminal UIs: terminal setup, an event
+// channel fed by the keyboard and file watchers, and markdown rendering.
+
+pub enum Event {
+    Key(KeyEvent),
+    Resize,
+    /// Something changed in a watched directory
+    Changed,
+}
+
+/// Full-screen terminal, restored when dropped, also on errors.
+pub struct Tui {
+    pub terminal: DefaultTerminal,
+    pub events: mpsc::UnboundedReceiver<Event>,
+    sender: mpsc::UnboundedSender<Event>,
+}
+
+impl Tui {
+    /// Switches to the alternate screen and starts reading the keyboard.
+    pub fn start() -> anyhow::Result<Self> {
+        use std::io::IsTerminal;
+        if !std::io::stdout().is_terminal() {
+            anyhow::bail!("This command needs an interactive terminal");
+        }
+
+        let terminal = ratatui::try_init()?;
+        let (sender, events) = mpsc::unbounded_channel();
+        let keys = sender.clone();
+        std::thread::spawn(move || {
+            // Ends when the receiver is gone
+            while !keys.is_closed() {
+                if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                    continue;
+                }
+                let sent = match event::read() {
+                    Ok(event::Event::Key(key)) if key.kind == event::KeyEventKind::Press => keys.send(Event::Key(key)),
+                    Ok(event::Event::Resize(..)) => keys.send(Event::Resize),
+                    Ok(_) => Ok(()),
+                    Err(_) => break,
+                };
+                if sent.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Tui { terminal, events, sender })
+    }
+
+    /// Sends `Event::Changed` whenever something under the directory changes.
+    /// Events stop when the returned watcher is dropped.
+    pub fn watch(&self, dir: &Path) -> anyhow::Result<notify::RecommendedWatcher> {
+        use notify::Watcher;
+        let sender = self.sender.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok_and(|e| !e.kind.is_access()) {
+                let _ = sender.send(Event::Changed);
+            }
+        })?;
+        watcher.watch(dir, notify::RecursiveMode::Recursive)?;
+        Ok(watcher)
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Renders markdown for a preview pane: headings, lists, quotes, code blocks,
+/// and `**bold**`, `*italic*` and `` `code` `` inline. The frontmatter is dimmed.
+pub fn markdown(source: &str) -> Text<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    let mut in_frontmatter = false;
+
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if i == 0 && line == "---" {
+            in_frontmatter = true;
+            lines.push(Line::styled(line.to_string(), dim));
+            continue;
+        }
+        if in_frontmatter {
+            in_frontmatter = line != "---";
+            lines.push(Line::styled(line.to_string(), dim));
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            lines.push(Line::styled(line.to_string(), dim));
+            continue;
+        }
+        if in_code {
+            lines.push(Line::styled(line.to_string(), Style::default().fg(Color::Yellow)));
+            continue;
+        }
+
+        let heading = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&heading) && trimmed[heading..].starts_with(' ') {
+            let color = match heading {
+                1 => Color::Cyan,
+                2 => Color::Green,
+                _ => Color::Blue,
+            };
+            lines.push(Line::styled(
+                trimmed[heading + 1..].to_string(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ));
+        } else if let Some(rest) = trimmed.strip_prefix("> ") {
+            let mut spans = vec![Span::styled("│ ", dim)];
+            spans.extend(inline(rest, Style::default().add_modifier(Modifier::ITALIC)));
+            lines.push(Line::from(spans));
+        } else if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|b| trimmed.strip_prefix(b)) {
+            let indent = " ".repeat(line.len() - trimmed.len());
+            let (marker, rest) = match rest {
+                r if r.starts_with("[ ] ") => ("☐ ", &r[4..]),
+                r if r.starts_with("[x] ") || r.starts_with("[X] ") => ("☑ ", &r[4..]),
+                r => ("• ", r),
+            };
+            let mut spans = vec![Span::raw(indent), Span::styled(marker, Style::default().fg(Color::Cyan))];
+            spans.extend(inline(rest, Style::default()));
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(inline(line, Style::default())));
+        }
+    }
+    Text::from(lines)
+}
+
+/// Splits a line at `**`, `*` and `` ` `` markers into styled spans.
+fn inline(text: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let next = ["**", "`", "*"]
+            .iter()
+            .filter_map(|marker| {
+                let start = rest.find(marker)?;
+                let end = rest[start + marker.len()..].find(marker)?;
+                Some((start, end, *marker))
+            })
+            .min_by_key(|(start, _, marker)| (*start, std::cmp::Reverse(marker.len())));
+        let Some((start, len, marker)) = next else {
+            spans.push(Span::styled(rest.to_string(), base));
+            break;
+        };
+        if start > 0 {
+            spans.push(Span::styled(rest[..start].to_string(), base));
+        }
+        let inner = &rest[start + marker.len()..start + marker.len() + len];
+        let style = match marker {
+            "**" => base.add_modifier(Modifier::BOLD),
+            "`" => base.fg(Color::Yellow),
+            _ => base.add_modifier(Modifier::ITALIC),
+        };
+        spans.push(Span::styled(inner.to_string(), style));
+        rest = &rest[start + 2 * marker.len() + len..];
+    }
+    spans
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use crate::agent_browser::BrowserOptions;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy)]
 pub enum EntityType {
@@ -42,13 +44,19 @@ impl std::str::FromStr for EntityType {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Repository {
     pub name: String,
     pub path: String,
+    /// The server's `.agelum/tests` folder as a folder of this machine: the server
+    /// runs here, or the profile maps the server's base path with `local_path`.
+    /// `None` when the test files can't be reached from here.
+    #[serde(skip)]
+    pub tests_dir: Option<PathBuf>,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct RepositoryResponse {
     pub repositories: Vec<Repository>,
@@ -56,17 +64,48 @@ pub struct RepositoryResponse {
     pub base_path: String,
     #[serde(default)]
     pub server_mode: bool,
+    /// Where the server keeps the tests of every repository; older servers
+    /// don't report it
+    #[serde(default)]
+    pub tests_dir: Option<String>,
     #[serde(default)]
     pub error: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// State directories of tasks under `.agelum/work/tasks`, in board order.
+pub const TASK_STATES: [&str; 5] = ["backlog", "pending", "doing", "done", "fixes"];
+
+/// Node of the tree returned by `/api/files`; paths are absolute on the server.
+#[derive(Deserialize, Debug)]
+pub struct FileNode {
+    pub path: String,
+    /// "file" or "directory"
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub children: Vec<FileNode>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesResponse {
+    pub tree: Option<FileNode>,
+    #[serde(default)]
+    pub root_path: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Task {
     pub id: String,
     pub title: String,
     pub state: String,
     #[serde(default)]
     pub epic: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    /// Markdown file of the task on the server's machine
+    #[serde(default)]
+    pub path: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -89,54 +128,507 @@ pub struct FileResponse {
 }
 
 // Test-related types
-#[derive(Deserialize, Serialize, Debug)]
-pub struct TestGroup {
-    pub id: String,
-    pub name: String,
-    pub description: Option<String>,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
+/// A test as listed in `.agelum/tests/index.json`. This is also the schema of the
+/// YAML specs synced by `agelum test pull|push`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Test {
     pub id: String,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Only present when the full test definition is fetched (`/api/tests/<id>`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<TestStep>,
+    /// Session, viewport and headed mode the test runs with; like the steps,
+    /// only in the full definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<BrowserOptions>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// A step as stored in the test's `test.json` (see `.agelum/doc/docs/test-step-types.md`).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct TestStep {
-    pub id: String,
-    pub command: String,
-    pub args: Vec<String>,
-    pub order: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Milliseconds the step may take; assertions poll until it expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(flatten)]
+    pub action: StepAction,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum StepAction {
+    Open { url: String },
+    Command { command: String },
+    Prompt { instruction: String },
+    /// Evaluated by the local runner, polling until it holds or the step's timeout expires
+    Assert {
+        #[serde(flatten)]
+        assertion: Assertion,
+    },
+    /// Any other action of the test engine (`click`, `wait`, `setViewport`...),
+    /// kept as written so it survives a save. These run on the server only.
+    #[serde(untagged)]
+    Other(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "check", rename_all = "camelCase")]
+pub enum Assertion {
+    /// The text appears in the page snapshot
+    Text { text: String },
+    /// An element matching the selector exists, or exactly `count` of them
+    Element {
+        selector: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        count: Option<usize>,
+    },
+    /// The current URL matches a regular expression
+    Url { pattern: String },
+    /// The page title equals the given text
+    Title { title: String },
+}
+
+impl TestStep {
+    /// Builds a step from command line words: `open <url>`, `prompt <instruction...>`,
+    /// `assert <check> <value...> [--count N] [--timeout MS]`, or any other
+    /// agent-browser command.
+    pub fn from_args(args: &[String]) -> anyhow::Result<Self> {
+        let action = match args {
+            [] => anyhow::bail!("A step needs a command"),
+            [cmd, url] if cmd == "open" => StepAction::Open { url: url.clone() },
+            [cmd, rest @ ..] if cmd == "prompt" => StepAction::Prompt {
+                instruction: rest.join(" "),
+            },
+            [cmd, rest @ ..] if cmd == "assert" => {
+                let (assertion, timeout) = parse_assert(rest)?;
+                let step = TestStep { id: None, name: None, timeout, action: StepAction::Assert { assertion } };
+                step.validate()?;
+                return Ok(step);
+            }
+            _ => StepAction::Command {
+                command: shell_words::join(args),
+            },
+        };
+        let step = TestStep { id: None, name: None, timeout: None, action };
+        step.validate()?;
+        Ok(step)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let (field, value) = match &self.action {
+            StepAction::Open { url } => ("url", url),
+            StepAction::Command { command } => ("command", command),
+            StepAction::Prompt { instruction } => ("instruction", instruction),
+            StepAction::Assert { assertion, .. } => match assertion {
+                Assertion::Text { text } => ("text", text),
+                Assertion::Element { selector, .. } => ("selector", selector),
+                Assertion::Url { pattern } => ("pattern", pattern),
+                Assertion::Title { title } => ("title", title),
+            },
+            StepAction::Other(fields) => match fields.get("action") {
+                Some(serde_json::Value::String(action)) => ("action", action),
+                _ => anyhow::bail!("Step has no action"),
+            },
+        };
+        if value.trim().is_empty() {
+            anyhow::bail!("Step '{}' has an empty {}", self, field);
+        }
+        if let StepAction::Assert { assertion: Assertion::Url { pattern }, .. } = &self.action {
+            regex::Regex::new(pattern)
+                .map_err(|e| anyhow::anyhow!("Step '{}' has an invalid pattern: {}", self, e))?;
+        }
+        if let StepAction::Assert { assertion: Assertion::Element { selector, .. } } = &self.action {
+            crate::runner::Selector::parse(selector)?;
+        }
+        Ok(())
+    }
+
+    /// Arguments to pass to agent-browser, or `None` for steps it can't run.
+    pub fn browser_args(&self) -> Option<Vec<String>> {
+        match &self.action {
+            StepAction::Open { url } => Some(vec!["open".to_string(), url.clone()]),
+            StepAction::Command { command } => Some(
+                shell_words::split(command)
+                    .unwrap_or_else(|_| command.split_whitespace().map(String::from).collect()),
+            ),
+            StepAction::Prompt { .. } | StepAction::Assert { .. } | StepAction::Other(_) => None,
+        }
+    }
+}
+
+/// The assertion and `--timeout` of `assert` step words.
+fn parse_assert(args: &[String]) -> anyhow::Result<(Assertion, Option<u64>)> {
+    let usage = "Usage: assert <text|element|url|title> <value> [--count N] [--timeout MS]";
+    let Some((check, rest)) = args.split_first() else {
+        anyhow::bail!(usage);
+    };
+
+    let mut words = Vec::new();
+    let mut count = None;
+    let mut timeout = None;
+    let mut rest = rest.iter();
+    while let Some(word) = rest.next() {
+        match word.as_str() {
+            "--count" | "--timeout" => {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("{} needs a value", word))?;
+                let value: u64 = value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("{} must be a number, got {}", word, value))?;
+                if word == "--count" {
+                    count = Some(value as usize);
+                } else {
+                    timeout = Some(value);
+                }
+            }
+            _ => words.push(word.as_str()),
+        }
+    }
+    let value = words.join(" ");
+
+    let assertion = match check.as_str() {
+        "text" => Assertion::Text { text: value },
+        "element" => Assertion::Element { selector: value, count },
+        "url" => Assertion::Url { pattern: value },
+        "title" => Assertion::Title { title: value },
+        _ => anyhow::bail!("Unknown assertion '{}'. {}", check, usage),
+    };
+    if count.is_some() && !matches!(assertion, Assertion::Element { .. }) {
+        anyhow::bail!("--count only applies to element assertions");
+    }
+    Ok((assertion, timeout))
+}
+
+impl std::fmt::Display for TestStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.action {
+            StepAction::Open { url } => write!(f, "open {}", url),
+            StepAction::Command { command } => write!(f, "{}", command),
+            StepAction::Prompt { instruction } => write!(f, "prompt {}", instruction),
+            StepAction::Assert { assertion } => {
+                // Quoted so the text parses back into the same step
+                match assertion {
+                    Assertion::Text { text } => write!(f, "assert text {}", shell_words::quote(text))?,
+                    Assertion::Element { selector, count } => {
+                        write!(f, "assert element {}", shell_words::quote(selector))?;
+                        if let Some(count) = count {
+                            write!(f, " --count {}", count)?;
+                        }
+                    }
+                    Assertion::Url { pattern } => write!(f, "assert url {}", shell_words::quote(pattern))?,
+                    Assertion::Title { title } => write!(f, "assert title {}", shell_words::quote(title))?,
+                }
+                if let Some(timeout) = self.timeout {
+                    write!(f, " --timeout {}", timeout)?;
+                }
+                Ok(())
+            }
+            StepAction::Other(fields) => {
+                let action = fields.get("action").and_then(|a| a.as_str()).unwrap_or("?");
+                write!(f, "{}", action)?;
+                for (key, value) in fields.iter().filter(|(key, _)| *key != "action") {
+                    match value {
+                        serde_json::Value::String(s) => write!(f, " {}={}", key, shell_words::quote(s))?,
+                        _ => write!(f, " {}={}", key, value)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct TestExecution {
     pub id: String,
     pub test_id: String,
-    pub timestamp: String,
+    pub started_at: String,
+    #[serde(default)]
+    pub completed_at: Option<String>,
     pub status: String,
+    #[serde(default)]
+    pub duration: Option<u64>,
+    #[serde(default)]
     pub error: Option<String>,
+    #[serde(default)]
+    pub screenshots: Vec<String>,
+    /// Output of the run, as recorded by the server
+    #[serde(default)]
+    pub logs: Vec<String>,
+}
+
+/// Result of a run made outside the server, reported through `/api/tests/<id>/finish`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionResult {
+    pub execution_id: String,
+    pub status: String,
+    pub logs: Vec<String>,
+    pub screenshots: Vec<String>,
+    pub started_at: String,
+    pub completed_at: String,
+}
+
+/// Step-by-step record of a local run, written as `log.json` in the execution's
+/// run folder and replayed by `agelum test log`. Secrets are masked.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionLog {
+    pub execution_id: String,
+    pub test_id: String,
+    pub status: String,
+    pub started_at: String,
+    pub completed_at: String,
+    pub steps: Vec<StepLog>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StepLog {
+    /// e.g. "Step 2" or "before_each 1"
+    pub label: String,
+    /// The step as written, with its placeholders
+    pub step: String,
+    /// Data row the step ran for, 1-based
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row: Option<usize>,
+    /// "passed", "failed" or "timedOut"
+    pub status: String,
+    /// Milliseconds
+    pub duration: u64,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Written as `attempt.json` next to an execution's `result.json` when a run is
+/// retried, so retries can be told apart from independent runs.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestAttempt {
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub first_execution_id: String,
+}
+
+// Recording types (`/api/tests/record/*`)
 #[derive(Deserialize, Debug)]
-pub struct TestGroupResponse {
-    pub groups: Vec<TestGroup>,
+#[serde(rename_all = "camelCase")]
+pub struct RecordExecuteResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub output: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct RecordCapture {
+    /// Base64 encoded PNG
+    #[serde(default)]
+    pub screenshot: String,
+    #[serde(default)]
+    pub snapshot: String,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct TestResponse {
-    pub tests: Vec<Test>,
+pub struct AIBackend {
+    pub id: String,
+    pub label: String,
+    pub model: String,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct TestStepResponse {
-    pub steps: Vec<TestStep>,
+#[serde(rename_all = "camelCase")]
+pub struct AIRecommendation {
+    /// "command" for a concrete agent-browser command, "prompt" when the step
+    /// was already executed by the agent and should stay a prompt step
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub instruction: Option<String>,
+    #[serde(default)]
+    pub explanation: String,
+    #[serde(default)]
+    pub step_description: String,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct TestExecutionResponse {
-    pub executions: Vec<TestExecution>,
+pub struct AgentModelsResponse {
+    pub models: Vec<String>,
+}
+
+/// Key for the `/api` endpoints, as listed by `/api/api-keys` (the secret itself
+/// is only returned once, on creation).
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub raw_key: String,
+}
+
+/// User settings as stored by the app in `~/.agelum/user-settings.json`; the
+/// keys are checked against `commands::settings` before writing.
+#[derive(Deserialize, Debug)]
+pub struct SettingsResponse {
+    pub settings: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `/api/version`: the app's version and the API routes it serves.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerVersion {
+    pub version: String,
+    #[serde(default)]
+    pub server_mode: bool,
+    #[serde(default)]
+    pub routes: Vec<String>,
+}
+
+/// `/api/app-status`: the app of a repository, as started from Agelum or found
+/// answering on the project's URL.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AppStatus {
+    pub is_running: bool,
+    /// Started from Agelum, rather than found on the URL
+    #[serde(default)]
+    pub is_managed: bool,
+    #[serde(default)]
+    pub is_url_ready: bool,
+    pub pid: Option<u32>,
+    pub started_at: Option<String>,
+    pub command: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(line: &str) -> anyhow::Result<TestStep> {
+        TestStep::from_args(&shell_words::split(line)?)
+    }
+
+    #[test]
+    fn steps_parse_back_from_their_display() {
+        for line in [
+            "open https://example.com/login",
+            "fill @e4 'admin@example.com'",
+            "fill '#password' 'two words'",
+            "prompt Check that the dashboard shows the welcome banner",
+            "assert text 'Welcome back' --timeout 5000",
+            "assert element 'cell \"Total due\"' --count 3",
+            "assert url '^https://example\\.com/home$'",
+            "assert title Dashboard",
+        ] {
+            let parsed = step(line).unwrap();
+            let reparsed = step(&parsed.to_string()).unwrap();
+            assert_eq!(reparsed, parsed, "{} shown as {}", line, parsed);
+        }
+    }
+
+    #[test]
+    fn parses_the_kind_of_step() {
+        assert_eq!(step("open https://example.com").unwrap().action, StepAction::Open {
+            url: "https://example.com".to_string()
+        });
+        assert_eq!(step("click @e2").unwrap().action, StepAction::Command { command: "click @e2".to_string() });
+        let assert = step("assert element listitem --count 2 --timeout 100").unwrap();
+        assert_eq!(assert.action, StepAction::Assert {
+            assertion: Assertion::Element { selector: "listitem".to_string(), count: Some(2) },
+        });
+        assert_eq!(assert.timeout, Some(100));
+    }
+
+    #[test]
+    fn keeps_engine_steps_it_does_not_know() {
+        let body = r##"[{"id": "s1", "action": "click", "selector": "#submit", "timeout": 3000},
+            {"action": "wait", "type": "time", "value": 500},
+            {"action": "open", "url": "https://example.com", "timeout": 10000}]"##;
+        let steps: Vec<TestStep> = serde_json::from_str(body).unwrap();
+        assert!(matches!(&steps[0].action, StepAction::Other(fields) if fields["selector"] == "#submit"));
+        assert_eq!(steps[0].timeout, Some(3000));
+        assert_eq!(steps[0].to_string(), "click selector='#submit'");
+        assert_eq!(steps[2].timeout, Some(10000));
+        assert!(steps.iter().all(|step| step.validate().is_ok()));
+
+        // Saved as read, through the steps route and through the YAML editor
+        let saved = serde_json::to_value(&steps).unwrap();
+        assert_eq!(saved, serde_json::from_str::<serde_json::Value>(body).unwrap());
+        let yaml: Vec<TestStep> = serde_yaml::from_str(&serde_yaml::to_string(&steps).unwrap()).unwrap();
+        assert_eq!(yaml, steps);
+    }
+
+    #[test]
+    fn parses_executions_as_the_server_lists_them() {
+        // `/api/tests/<id>/executions` returns the runs' result.json files as they are
+        let body = r#"[{"id": "exec-2", "testId": "t1", "startedAt": "2026-01-02T00:00:00Z",
+            "completedAt": "2026-01-02T00:00:01Z", "status": "failed", "duration": 1200,
+            "error": "Step 2 failed", "screenshots": ["/api/tests/artifacts/1700-home.png"]},
+            {"id": "exec-1", "testId": "t1", "startedAt": "2026-01-01T00:00:00Z", "status": "running"}]"#;
+        let executions: Vec<TestExecution> = serde_json::from_str(body).unwrap();
+        assert_eq!(executions.len(), 2);
+        assert_eq!(executions[0].started_at, "2026-01-02T00:00:00Z");
+        assert_eq!(executions[0].screenshots, ["/api/tests/artifacts/1700-home.png"]);
+        assert_eq!(executions[1].duration, None);
+    }
+
+    #[test]
+    fn reads_the_browser_settings_from_the_test_definition() {
+        let body = r#"{"id": "t1", "name": "Login", "steps": [],
+            "browser": {"headed": true, "viewport": {"width": 1280, "height": 720}}}"#;
+        let test: Test = serde_json::from_str(body).unwrap();
+        let browser = test.browser.clone().unwrap();
+        assert_eq!(browser.session, None);
+        assert_eq!(browser.headed, Some(true));
+        assert_eq!(browser.viewport.map(|v| (v.width, v.height)), Some((1280, 720)));
+        // Saved back unchanged, and left out of tests that don't set it
+        assert_eq!(serde_json::from_value::<Test>(serde_json::to_value(&test).unwrap()).unwrap().browser, Some(browser));
+        let plain: Test = serde_json::from_str(r#"{"id": "t2", "name": "Home"}"#).unwrap();
+        assert!(serde_json::to_value(&plain).unwrap().get("browser").is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_steps() {
+        assert!(TestStep::from_args(&[]).is_err());
+        assert!(step("assert size 3").is_err());
+        assert!(step("assert text Hello --count 2").is_err());
+        assert!(step("assert text Hello --timeout soon").is_err());
+        assert!(step("assert url '('").is_err());
+        assert!(step("assert element '.row > td'").is_err());
+        assert!(step("prompt").is_err());
+    }
 }
@@ -0,0 +1,250 @@
+use crate::api;
+use crate::types::{Assertion, Repository, StepAction, TestStep};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+// `${NAME}` placeholders in test steps. A placeholder is resolved from the current
+// data row, then the secrets file, then the environment. Values from the secrets
+// file and the environment are masked in what the run prints and records, since CI
+// passes secrets through the environment as often as through the secrets file.
+// Data rows hold test data, which stays readable.
+
+/// Data table of a test, next to its `test.json`: an array of rows, each an object
+/// of variable names to values. The test runs once per row.
+pub const DATA_FILE: &str = "data.json";
+/// `NAME=value` lines shared by all tests of a repository. Must not be committed.
+pub const SECRETS_FILE: &str = "secrets.env";
+
+const MASK: &str = "********";
+/// Shorter values are not masked: hiding every "1" or "on" would garble the
+/// output without protecting anything.
+const MIN_MASKED_LEN: usize = 4;
+
+pub type DataRow = BTreeMap<String, String>;
+
+pub struct Variables {
+    row: DataRow,
+    secrets: BTreeMap<String, String>,
+    /// Values taken from the environment so far
+    from_env: Mutex<BTreeSet<String>>,
+}
+
+impl Variables {
+    pub fn new(row: DataRow, secrets: BTreeMap<String, String>) -> Self {
+        Variables {
+            row,
+            secrets,
+            from_env: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.row.get(name).or_else(|| self.secrets.get(name)) {
+            return Some(value.clone());
+        }
+        let value = std::env::var(name).ok()?;
+        self.from_env.lock().unwrap_or_else(|e| e.into_inner()).insert(value.clone());
+        Some(value)
+    }
+
+    /// Replaces every `${NAME}` in the text, failing on undefined names.
+    pub fn interpolate(&self, text: &str) -> anyhow::Result<String> {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 2..start + 2 + len];
+            let value = self
+                .resolve(name)
+                .ok_or_else(|| anyhow::anyhow!("Undefined variable ${{{}}}", name))?;
+            result.push_str(&rest[..start]);
+            result.push_str(&value);
+            rest = &rest[start + 3 + len..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Copy of the step with its placeholders resolved. Command arguments are
+    /// resolved one by one, so values with spaces stay a single argument.
+    pub fn interpolate_step(&self, step: &TestStep) -> anyhow::Result<TestStep> {
+        let action = match &step.action {
+            StepAction::Open { url } => StepAction::Open { url: self.interpolate(url)? },
+            StepAction::Command { .. } => {
+                let args = step
+                    .browser_args()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|arg| self.interpolate(arg))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                StepAction::Command { command: shell_words::join(args) }
+            }
+            StepAction::Prompt { instruction } => StepAction::Prompt {
+                instruction: self.interpolate(instruction)?,
+            },
+            StepAction::Assert { assertion } => StepAction::Assert {
+                assertion: match assertion {
+                    Assertion::Text { text } => Assertion::Text { text: self.interpolate(text)? },
+                    Assertion::Element { selector, count } => Assertion::Element {
+                        selector: self.interpolate(selector)?,
+                        count: *count,
+                    },
+                    Assertion::Url { pattern } => Assertion::Url { pattern: self.interpolate(pattern)? },
+                    Assertion::Title { title } => Assertion::Title { title: self.interpolate(title)? },
+                },
+            },
+            StepAction::Other(fields) => StepAction::Other(fields.clone()),
+        };
+        Ok(TestStep { action, ..step.clone() })
+    }
+
+    /// Hides the secrets, and the environment values substituted so far, in text
+    /// that is printed or recorded.
+    pub fn mask(&self, text: &str) -> String {
+        let from_env = self.from_env.lock().unwrap_or_else(|e| e.into_inner());
+        let values: BTreeSet<&String> = self
+            .secrets
+            .values()
+            .chain(from_env.iter())
+            .filter(|v| v.chars().count() >= MIN_MASKED_LEN)
+            .collect();
+        let mut values: Vec<&String> = values.into_iter().collect();
+        // Longest first, so a value containing another is fully hidden
+        values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        values
+            .into_iter()
+            .fold(text.to_string(), |text, value| text.replace(value.as_str(), MASK))
+    }
+}
+
+pub fn secrets_path(repo: &Repository) -> Option<PathBuf> {
+    Some(api::tests_dir(repo)?.join(SECRETS_FILE))
+}
+
+/// Reads the secrets file of a repository, if there is one.
+pub fn read_secrets(repo: &Repository) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut secrets = BTreeMap::new();
+    let Some(path) = secrets_path(repo) else {
+        return Ok(secrets);
+    };
+    if !path.exists() {
+        return Ok(secrets);
+    }
+    if !is_git_ignored(&path) {
+        eprintln!(
+            "⚠ {} is not ignored by git; add it to .gitignore so secrets aren't committed",
+            path.display()
+        );
+    }
+
+    for (i, line) in std::fs::read_to_string(&path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            anyhow::bail!("{}:{}: expected NAME=value", path.display(), i + 1);
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        secrets.insert(name.trim().to_string(), value.to_string());
+    }
+    Ok(secrets)
+}
+
+/// Reads the data table in a test folder. A test without one runs once.
+pub fn read_data(test_dir: &Path) -> anyhow::Result<Vec<DataRow>> {
+    let path = test_dir.join(DATA_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let rows: Vec<BTreeMap<String, serde_json::Value>> =
+        serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(name, value)| match value {
+                    serde_json::Value::String(s) => (name, s),
+                    other => (name, other.to_string()),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn is_git_ignored(path: &Path) -> bool {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["check-ignore", "-q"])
+        .arg(path)
+        .stderr(Stdio::null())
+        .status()
+        // Outside a git repository there is nothing to commit the file to
+        .map(|status| status.success() || status.code() == Some(128))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(row: &[(&str, &str)], secrets: &[(&str, &str)]) -> Variables {
+        let pairs = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Variables::new(pairs(row), pairs(secrets))
+    }
+
+    #[test]
+    fn interpolates_from_the_row_then_the_secrets() {
+        let vars = vars(&[("EMAIL", "admin@example.com"), ("PASSWORD", "from-row")], &[("PASSWORD", "from-secrets")]);
+        assert_eq!(vars.interpolate("${EMAIL}:${PASSWORD}").unwrap(), "admin@example.com:from-row");
+        assert_eq!(vars.interpolate("no placeholders, ${ unclosed").unwrap(), "no placeholders, ${ unclosed");
+    }
+
+    #[test]
+    fn fails_on_undefined_variables() {
+        let error = vars(&[], &[]).interpolate("${AGELUM_TEST_UNDEFINED}").unwrap_err();
+        assert_eq!(error.to_string(), "Undefined variable ${AGELUM_TEST_UNDEFINED}");
+    }
+
+    #[test]
+    fn masks_secrets_before_they_are_used() {
+        let vars = vars(&[], &[("TOKEN", "s3cret"), ("EMPTY", "")]);
+        assert_eq!(vars.mask("token s3cret in output"), "token ******** in output");
+    }
+
+    #[test]
+    fn masks_values_from_the_environment() {
+        // SAFETY: the name is only used by this test
+        unsafe { std::env::set_var("AGELUM_TEST_ENV_SECRET", "hunter2") };
+        let vars = vars(&[("USER", "admin")], &[]);
+
+        assert_eq!(vars.mask("hunter2"), "hunter2");
+        assert_eq!(vars.interpolate("${USER}:${AGELUM_TEST_ENV_SECRET}").unwrap(), "admin:hunter2");
+        assert_eq!(vars.mask("login admin:hunter2 failed"), "login admin:******** failed");
+    }
+
+    #[test]
+    fn leaves_data_rows_and_short_values_readable() {
+        let vars = vars(&[("ROW", "3"), ("ENABLED", "true")], &[("PIN", "42"), ("TOKEN", "s3cret")]);
+        vars.interpolate("${ROW} ${ENABLED} ${PIN}").unwrap();
+        assert_eq!(vars.mask("Step 3 failed: true, 42, s3cret"), "Step 3 failed: true, 42, ********");
+    }
+
+    #[test]
+    fn masks_the_longest_value_first() {
+        let vars = vars(&[], &[("SHORT", "abcd"), ("LONG", "abcdef")]);
+        assert_eq!(vars.mask("abcdef abcd"), "******** ********");
+    }
+}